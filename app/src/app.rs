@@ -1,139 +1,749 @@
+use std::fs;
 use std::io;
-use crossterm::{terminal::{enable_raw_mode, disable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen}, execute, event::{self, Event, KeyCode}};
+use std::path::Path;
+use std::process::Command;
+use crossterm::{terminal::{enable_raw_mode, disable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen}, execute, event::{self, DisableBracketedPaste, EnableBracketedPaste, Event}};
 use ratatui::{backend::CrosstermBackend, Terminal};
-use ratatui::widgets::ListState;
 
+use crate::compose::{self, Draft};
+use crate::compose::builder::RawMessage;
+use crate::keymap::{Action, Context, Dispatcher};
+use crate::events::AppEvent;
+use crate::gmail::SendAs;
+use crate::storage::contacts::ContactBook;
 use crate::ui;
+use crate::ui::composer::{Composer, ComposerAction};
+use crate::ui::review::{ReviewAction, ReviewView};
+use crate::ui::contacts::{ContactsAction, ContactsView};
+use crate::ui::drafts::{DraftsAction, DraftsView};
+use crate::ui::outbox::{OutboxAction, OutboxView};
+use crate::ui::search::{PromptOutcome, SearchPrompt};
+use crate::ui::single_mail::{ReaderAction, ReaderView};
+use crate::ui::status::{self, MessageLogView};
+use crate::ui::thread::ThreadView;
+use crate::ui::ListMode;
 
-pub fn run() -> Result<(), io::Error> {
-    // ask user which provider to use via TUI and attempt login if requested
-    match crate::ui::login::prompt_provider() {
-        Ok(crate::ui::login::Provider::Google) => {
-            let client_id = std::env::var("MAIL_OAUTH_CLIENT_ID").ok();
-            let client_secret = std::env::var("MAIL_OAUTH_CLIENT_SECRET").ok();
-            if let (Some(id), Some(sec)) = (client_id, client_secret) {
-                match crate::auth::oauth_wrapper::oauth_login(&id, &sec) {
-                    Ok(token) => println!("OAuth token obtained (length {}), continuing...", token.len()),
-                    Err(e) => eprintln!("OAuth login failed: {}", e),
-                }
-            } else {
-                println!("MAIL_OAUTH_CLIENT_ID/MAIL_OAUTH_CLIENT_SECRET not set; set them or choose Skip.");
+// how many messages a `/` search pulls in
+const SEARCH_MAX_RESULTS: usize = 50;
+const DRAFTS_MAX_RESULTS: usize = 50;
+// how often the outbox worker looks for due retries when nothing wakes it
+const OUTBOX_POLL: std::time::Duration = std::time::Duration::from_secs(15);
+
+type Tui = Terminal<CrosstermBackend<io::Stdout>>;
+
+/// Run the TUI. With `offline`, skip login and syncing and show only what is in the local cache.
+/// With `compose`, open the composer on that draft and exit once it is sent or discarded.
+pub fn run(offline: bool, compose: Option<Draft>) -> Result<(), io::Error> {
+    // workers started from here on report to this loop
+    let events = crate::events::subscribe();
+    // render from the cache right away; sync results are merged in as they arrive
+    let inbox = crate::storage::cache::load_inbox(crate::storage::cache::INBOX_LIMIT).unwrap_or_else(|e| {
+        log::warn!("failed to read message cache: {}", e);
+        Vec::new()
+    });
+
+    if !offline {
+        // a configured account picks the provider; otherwise ask via TUI and attempt login if requested
+        let cfg = crate::config::get();
+        let choice = match cfg.account() {
+            Some(a) => Ok(match a.provider {
+                crate::config::Provider::Gmail => crate::ui::login::Provider::Google,
+                crate::config::Provider::Outlook => crate::ui::login::Provider::Outlook,
+            }),
+            None => crate::ui::login::prompt_provider(),
+        };
+        match choice {
+            Ok(crate::ui::login::Provider::Google) => {
+                if let Some((id, sec)) = cfg.credentials() {
+                    match crate::auth::oauth_wrapper::oauth_login(&id, &sec) {
+                        Ok(token) => println!("OAuth token obtained (length {}), continuing...", token.len()),
+                        Err(e) => {
+                            log::error!("OAuth login failed: {}", e);
+                            eprintln!("OAuth login failed: {}", e);
+                        }
+                    }
+                } else {
+                    println!("No OAuth client configured; set client_id/client_secret in {} or MAIL_OAUTH_CLIENT_ID/MAIL_OAUTH_CLIENT_SECRET, or choose Skip.", crate::config::config_file().display());
+                }
+            }
+            Ok(crate::ui::login::Provider::Outlook) => {
+                println!("Outlook login is not implemented yet. Skipping.");
+            }
+            Ok(crate::ui::login::Provider::Skip) | Err(_) => {
+                println!("Skipping login");
             }
         }
-        Ok(crate::ui::login::Provider::Outlook) => {
-            println!("Outlook login is not implemented yet. Skipping.");
-        }
-        Ok(crate::ui::login::Provider::Skip) | Err(_) => {
-            println!("Skipping login");
-        }
+        spawn_outbox_worker();
+        spawn_identity_fetch();
     }
 
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen)?;
+    execute!(stdout, EnterAlternateScreen, EnableBracketedPaste)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let mut list_state = ListState::default();
+    if let Some(mut draft) = compose {
+        let ids = identities(offline);
+        if draft.from.is_empty()
+            && let Some(id) = compose::pick_identity(&ids, None)
+        {
+            draft.use_identity(id);
+        }
+        let result = compose_and_send(&mut terminal, draft, &ids);
+        disable_raw_mode()?;
+        execute!(terminal.backend_mut(), DisableBracketedPaste, LeaveAlternateScreen)?;
+        result.map_err(|e| io::Error::other(e.to_string()))?;
+        if !offline {
+            flush_outbox();
+        }
+        let waiting = crate::storage::outbox::list();
+        if !waiting.is_empty() {
+            println!("{} message(s) waiting in the outbox; they are sent the next time mailtui runs online.", waiting.len());
+        }
+        return Ok(());
+    }
 
-    loop {
-        terminal.draw(|f| ui::draw(f, &mut list_state))?;
+    let mut state = ui::AppState::new(inbox, &crate::config::get().layout);
+    let mut search_prompt: Option<SearchPrompt> = None;
+    let mut keys = Dispatcher::default();
+    let mut show_help = false;
+    let mut dirty = true;
 
-        if event::poll(std::time::Duration::from_millis(100))? {
-            if let Event::Key(key) = event::read()? {
-                if key.code == KeyCode::Char('q') {
-                    break;
+    loop {
+        while let Ok(ev) = events.try_recv() {
+            dirty |= state.apply(ev);
+        }
+        // only redraw when something changed
+        if dirty || status::ticking() {
+            terminal.draw(|f| {
+                ui::draw(f, &mut state);
+                if let Some(prompt) = &search_prompt {
+                    prompt.render(f);
+                }
+                if show_help {
+                    ui::help::render(f, "Keys", Context::List);
                 }
+            })?;
+            dirty = false;
+        }
 
-                match key.code {
-                    KeyCode::Char('c') => {
-                        // compose email via external editor
-                        if let Err(e) = compose_and_send() {
-                            eprintln!("compose/send failed: {}", e);
+        if !event::poll(std::time::Duration::from_millis(100))? {
+            continue;
+        }
+        // resizes, and keys that open other screens, need the list drawn again
+        let ev = event::read()?;
+        dirty = true;
+        if let Event::Key(key) = ev {
+            if show_help {
+                show_help = false;
+                continue;
+            }
+            // the search prompt takes all keys while open
+            if let Some(prompt) = search_prompt.as_mut() {
+                match prompt.handle_key(key.code) {
+                    PromptOutcome::Continue => {}
+                    PromptOutcome::Cancel => search_prompt = None,
+                    PromptOutcome::Submit(query) => {
+                        search_prompt = None;
+                        if let Err(e) = crate::storage::search_history::push_history(&query) {
+                            status::error(format!("failed to save search history: {}", e));
                         }
+                        state.begin_search(&query);
+                        start_search(query, offline);
+                    }
+                }
+                continue;
+            }
+
+            let action = keys.feed(&crate::config::get().keybindings, Context::List, key).first().copied();
+            state.pending_keys = keys.pending();
+            match action {
+                // back to the mailbox
+                Some(Action::Back) if state.search_active() => state.clear_search(),
+                Some(Action::Quit) => break,
+                Some(Action::Help) => show_help = true,
+                Some(a @ (Action::Up | Action::Down | Action::PageUp | Action::PageDown | Action::Top | Action::Bottom)) => {
+                    dirty = state.move_selection(a);
+                }
+                Some(Action::Search) => {
+                    search_prompt = Some(SearchPrompt::new(crate::storage::search_history::load_history()));
+                }
+                Some(Action::ToggleThreads) => state.toggle_mode(),
+                Some(Action::TogglePreview) => state.toggle_preview(),
+                Some(Action::GrowPreview) => dirty = state.resize_preview(1),
+                Some(Action::ShrinkPreview) => dirty = state.resize_preview(-1),
+                Some(Action::Compose) => {
+                    // compose a new email
+                    let ids = identities(offline);
+                    let mut draft = Draft::default();
+                    if let Some(id) = compose::pick_identity(&ids, None) {
+                        draft.use_identity(id);
+                    }
+                    if let Err(e) = compose_and_send(&mut terminal, draft, &ids) {
+                        status::error(format!("compose/send failed: {}", e));
                     }
-                    KeyCode::Enter => {
-                        let sel = list_state.selected().map(|i| i/2).unwrap_or(0);
-                        if let Some(mail) = ui::get_message(sel) {
-                            // fullscreen view loop
-                            loop {
-                                terminal.draw(|f| ui::render_message_fullscreen(f, &mail))?;
-                                if event::poll(std::time::Duration::from_millis(100))? {
-                                    if let Event::Key(k) = event::read()? {
-                                        match k.code {
-                                            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Enter => break,
-                                            _ => {}
+                }
+                Some(Action::Drafts) => {
+                    let mut view = DraftsView::new(load_drafts(offline));
+                    loop {
+                        terminal.draw(|f| view.render(f))?;
+                        if !event::poll(std::time::Duration::from_millis(100))? {
+                            continue;
+                        }
+                        let Event::Key(k) = event::read()? else { continue };
+                        match view.handle_key(k) {
+                            DraftsAction::None => {}
+                            DraftsAction::Close => break,
+                            DraftsAction::Resume(d) => {
+                                match resume_draft(&d) {
+                                    Ok(draft) => {
+                                        if let Err(e) = compose_and_send(&mut terminal, draft, &identities(offline)) {
+                                            status::error(format!("compose/send failed: {}", e));
                                         }
                                     }
+                                    // resuming without them would drop the attachments on the next save
+                                    Err(e) => status::error(format!("cannot resume draft, its attachments failed to download: {}", e)),
+                                }
+                                view = DraftsView::new(load_drafts(offline));
+                            }
+                            DraftsAction::Delete(d) => {
+                                let res = access_token().and_then(|token| crate::gmail::delete_draft(&token, &d.id));
+                                if let Err(e) = res {
+                                    status::error(format!("failed to delete draft: {}", e));
+                                }
+                                view = DraftsView::new(load_drafts(offline));
+                            }
+                        }
+                    }
+                }
+                Some(Action::Outbox) => {
+                    let mut view = OutboxView::new(crate::storage::outbox::list());
+                    loop {
+                        terminal.draw(|f| view.render(f))?;
+                        // the worker updates entries in the background
+                        if !event::poll(std::time::Duration::from_millis(250))? {
+                            view.reload(crate::storage::outbox::list());
+                            continue;
+                        }
+                        let Event::Key(k) = event::read()? else { continue };
+                        let result = match view.handle_key(k) {
+                            OutboxAction::None => continue,
+                            OutboxAction::Close => break,
+                            OutboxAction::Retry(id) => crate::storage::outbox::retry_now(&id).map(|_| "queued for retry".to_string()),
+                            OutboxAction::Delete(id) => crate::storage::outbox::take(&id).map(|_| "deleted".to_string()),
+                            OutboxAction::Edit(id) => match crate::storage::outbox::take(&id) {
+                                Ok(entry) => {
+                                    if let Err(e) = compose_and_send(&mut terminal, entry.draft, &identities(offline)) {
+                                        status::error(format!("compose/send failed: {}", e));
+                                    }
+                                    Ok(String::new())
                                 }
+                                Err(e) => Err(e),
+                            },
+                        };
+                        view.message = match result {
+                            Ok(m) if m.is_empty() => None,
+                            Ok(m) => Some(m),
+                            Err(e) => Some(format!("failed: {}", e)),
+                        };
+                        view.reload(crate::storage::outbox::list());
+                    }
+                }
+                Some(Action::MessageLog) => {
+                    let mut view = MessageLogView::new(status::take_log());
+                    loop {
+                        terminal.draw(|f| view.render(f))?;
+                        if event::poll(std::time::Duration::from_millis(100))?
+                            && let Event::Key(k) = event::read()?
+                            && view.handle_key(k)
+                        {
+                            break;
+                        }
+                    }
+                }
+                Some(Action::Contacts) => {
+                    let mut view = ContactsView::new(ContactBook::load().contacts);
+                    loop {
+                        terminal.draw(|f| view.render(f))?;
+                        if !event::poll(std::time::Duration::from_millis(100))? {
+                            continue;
+                        }
+                        let Event::Key(k) = event::read()? else { continue };
+                        let result = match view.handle_key(k) {
+                            ContactsAction::None => continue,
+                            ContactsAction::Close => break,
+                            ContactsAction::Delete(email) => crate::storage::contacts::update(|book| {
+                                book.remove(&email);
+                                true
+                            })
+                            .map(|_| format!("deleted {}", email)),
+                            ContactsAction::Save { old, address } => {
+                                let mut saved = Err(String::new());
+                                crate::storage::contacts::update(|book| {
+                                    saved = book.set(old.as_deref(), &address);
+                                    saved.is_ok()
+                                })
+                                .map_err(|e| e.to_string())
+                                .and(saved)
+                                .map(|a| format!("saved {}", a))
+                                .map_err(std::io::Error::other)
+                            }
+                            ContactsAction::Import(path) => {
+                                let mut imported = Ok(0);
+                                crate::storage::contacts::update(|book| {
+                                    imported = book.import(&compose::expand_home(&path));
+                                    matches!(imported, Ok(n) if n > 0)
+                                })
+                                .and(imported)
+                                .map(|n| format!("imported {} addresses from {}", n, path))
+                            }
+                        };
+                        view.message = Some(result.unwrap_or_else(|e| format!("failed: {}", e)));
+                        view.reload(ContactBook::load().contacts);
+                    }
+                }
+                Some(a @ (Action::Reply | Action::ReplyAll | Action::Forward)) => {
+                    if let Some(orig) = state.selected_mail() {
+                        let ids = identities(offline);
+                        let own: Vec<String> = ids.iter().map(|i| i.email.clone()).collect();
+                        let mut draft = match a {
+                            Action::Reply => compose::reply(&orig, false, &[]),
+                            Action::ReplyAll => compose::reply(&orig, true, &own),
+                            _ => compose::forward(&orig),
+                        };
+                        if let Some(id) = compose::pick_identity(&ids, Some(&orig)) {
+                            draft.use_identity(id);
+                        }
+                        if let Err(e) = compose_and_send(&mut terminal, draft, &ids) {
+                            status::error(format!("compose/send failed: {}", e));
+                        }
+                    }
+                }
+                Some(Action::Open) => {
+                    if state.mode() == ListMode::Threads
+                        && let Some(summary) = state.selected_thread()
+                    {
+                        let messages = match summary.thread_id.as_deref().map(|t| load_thread(t, offline)) {
+                            Some(Ok(msgs)) if !msgs.is_empty() => msgs,
+                            Some(Err(e)) => {
+                                status::error(format!("failed to fetch thread: {}", e));
+                                vec![summary.latest]
+                            }
+                            _ => vec![summary.latest],
+                        };
+                        let mut view = ThreadView::new(messages);
+                        loop {
+                            terminal.draw(|f| view.render(f))?;
+                            if event::poll(std::time::Duration::from_millis(100))?
+                                && let Event::Key(k) = event::read()?
+                                && view.handle_key(k)
+                            {
+                                break;
+                            }
+                        }
+                    } else if state.mode() == ListMode::Messages
+                        && let Some(mail) = state.selected_mail()
+                    {
+                        let mut view = ReaderView::new(mail);
+                        loop {
+                            terminal.draw(|f| view.render(f))?;
+                            if !event::poll(std::time::Duration::from_millis(100))? {
+                                continue;
+                            }
+                            let Event::Key(k) = event::read()? else { continue };
+                            // n/p step through the list; the list follows along
+                            let moved = match view.handle_key(k) {
+                                ReaderAction::None => false,
+                                ReaderAction::Close => break,
+                                ReaderAction::Next => state.select_next(),
+                                ReaderAction::Prev => state.select_prev(),
+                            };
+                            if moved && let Some(mail) = state.selected_mail() {
+                                view = ReaderView::new(mail);
                             }
                         }
                     }
-                    other => ui::handle_key(&mut list_state, other, ui::message_count()),
                 }
+                Some(_) | None => {}
             }
         }
     }
 
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), DisableBracketedPaste, LeaveAlternateScreen)?;
+    Ok(())
+}
+
+// run the query off the UI thread; results come back as an event. Offline, or when
+// Gmail can't be reached, the local index over cached mail answers instead.
+fn start_search(query: String, offline: bool) {
+    std::thread::spawn(move || {
+        let local = || crate::storage::index::search(&query, SEARCH_MAX_RESULTS).map_err(|e| e.to_string());
+        let result = if offline {
+            local()
+        } else {
+            let remote = crate::token_store::load_token()
+                .map_err(|e| format!("no saved token: {}", e))
+                .and_then(|saved| {
+                    crate::gmail::search(&saved.access_token, &query, SEARCH_MAX_RESULTS).map_err(|e| e.to_string())
+                });
+            if let Ok(msgs) = &remote {
+                let _ = crate::storage::contacts::update(|book| book.record_messages(msgs));
+                let _ = crate::storage::cache::store_messages(msgs, false);
+            }
+            remote.or_else(|e| match local() {
+                Ok(msgs) if !msgs.is_empty() => Ok(msgs),
+                _ => Err(e),
+            })
+        };
+        if let Err(e) = &result {
+            status::error(format!("search for \"{}\" failed: {}", query, e));
+        }
+        crate::events::send(AppEvent::SearchResults { query, result });
+    });
+}
+
+fn load_drafts(offline: bool) -> Result<Vec<crate::gmail::GmailDraft>, String> {
+    if offline {
+        return Err("drafts live on the server and aren't available offline".into());
+    }
+    let saved = crate::token_store::load_token().map_err(|e| format!("no saved token: {}", e))?;
+    crate::gmail::list_drafts(&saved.access_token, DRAFTS_MAX_RESULTS).map_err(|e| e.to_string())
+}
+
+// a saved draft as an editable one, its attachments downloaded to a temporary directory
+fn resume_draft(d: &crate::gmail::GmailDraft) -> Result<Draft, Box<dyn std::error::Error + Send + Sync>> {
+    let mut draft = Draft::from_gmail_draft(d);
+    if d.attachments.is_empty() {
+        return Ok(draft);
+    }
+    let access = access_token()?;
+    let dir = std::env::temp_dir().join(format!("mailtui-draft-{}", d.id));
+    for (i, a) in d.attachments.iter().enumerate() {
+        let data = crate::gmail::fetch_attachment(&access, &d.message.id, a)?;
+        // one directory per file: the name is what gets sent, and a draft may attach two of the same name
+        let name = std::path::Path::new(&a.filename).file_name().map(|n| n.to_os_string()).unwrap_or_else(|| "attachment".into());
+        let path = dir.join(i.to_string()).join(name);
+        fs::create_dir_all(path.parent().unwrap_or(&dir))?;
+        fs::write(&path, data)?;
+        draft.attachments.push(path.to_string_lossy().into_owned());
+    }
+    Ok(draft)
+}
+
+// fetch the conversation, falling back to whatever the cache has; only the cache when offline
+fn load_thread(thread_id: &str, offline: bool) -> Result<Vec<crate::gmail::SimpleMail>, Box<dyn std::error::Error + Send + Sync>> {
+    if offline {
+        return Ok(crate::storage::cache::load_thread(thread_id)?);
+    }
+    let fetched = crate::token_store::load_token()
+        .map_err(|e| e.into())
+        .and_then(|saved| crate::gmail::fetch_thread(&saved.access_token, thread_id));
+    match fetched {
+        Ok(msgs) => {
+            let _ = crate::storage::cache::store_messages(&msgs, false);
+            Ok(msgs)
+        }
+        Err(e) => match crate::storage::cache::load_thread(thread_id) {
+            Ok(cached) if !cached.is_empty() => Ok(cached),
+            _ => Err(e),
+        },
+    }
+}
+
+// send-as identities fetched in the background at startup; `identities` answers from here
+static IDENTITIES: std::sync::Mutex<Option<Vec<SendAs>>> = std::sync::Mutex::new(None);
+
+// the account's send-as identities: the fetched ones once `spawn_identity_fetch` is done, else just the
+// configured account email (also when offline). Their addresses are also dropped from reply-all recipients.
+pub fn identities(offline: bool) -> Vec<SendAs> {
+    if offline {
+        return configured_identities();
+    }
+    let cached = IDENTITIES.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    cached.unwrap_or_else(configured_identities)
+}
+
+fn spawn_identity_fetch() {
+    std::thread::spawn(|| {
+        fetch_identities();
+    });
+}
+
+/// Ask the server for the send-as identities, falling back to the primary address (or the
+/// configured one) when the settings can't be read, and remember them for `identities`. Blocks.
+pub fn fetch_identities() -> Vec<SendAs> {
+    let ids = match access_token() {
+        Err(_) => configured_identities(),
+        Ok(token) => match crate::gmail::fetch_send_as(&token) {
+            Ok(ids) if !ids.is_empty() => ids,
+            _ => match crate::gmail::fetch_profile_email(&token) {
+                Ok(email) => vec![SendAs { email, display_name: None, reply_to: None, signature: None, is_default: true }],
+                Err(_) => configured_identities(),
+            },
+        },
+    };
+    *IDENTITIES.lock().unwrap_or_else(|e| e.into_inner()) = Some(ids.clone());
+    ids
+}
+
+fn configured_identities() -> Vec<SendAs> {
+    crate::config::get()
+        .account()
+        .and_then(|a| a.email.clone())
+        .map(|email| SendAs { email, display_name: None, reply_to: None, signature: None, is_default: true })
+        .into_iter()
+        .collect()
+}
+
+// hand the terminal to a child process: leave the alternate screen and raw mode,
+// run `f`, then restore both and force a full redraw
+fn with_suspended_tui<T>(terminal: &mut Tui, f: impl FnOnce() -> T) -> io::Result<T> {
     disable_raw_mode()?;
     execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    let out = f();
+    enable_raw_mode()?;
+    execute!(terminal.backend_mut(), EnterAlternateScreen)?;
+    terminal.clear()?;
+    Ok(out)
+}
+
+fn edit_in_editor(terminal: &mut Tui, path: &Path) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let editor = crate::config::get().editor().unwrap_or_else(|| String::from("nano"));
+    let status = with_suspended_tui(terminal, || Command::new(&editor).arg(path).status())??;
+    if !status.success() {
+        return Err(format!("editor exited with status: {}", status).into());
+    }
     Ok(())
 }
 
-fn compose_and_send() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    use std::fs;
-    use std::io::Write;
-    use std::process::Command;
+// compose.composer (or MAIL_COMPOSER) picks the composer; without it, use the editor when
+// one is configured and the built-in composer otherwise
+fn use_builtin_composer() -> bool {
+    let cfg = crate::config::get();
+    match cfg.compose.composer {
+        Some(crate::config::ComposerKind::Builtin) => true,
+        Some(crate::config::ComposerKind::External) => false,
+        None => cfg.editor().is_none(),
+    }
+}
+
+fn edit_in_composer(terminal: &mut Tui, draft: &mut Draft) -> io::Result<()> {
+    let mut composer = Composer::new(draft, ContactBook::load());
+    loop {
+        terminal.draw(|f| composer.render(f))?;
+        if !event::poll(std::time::Duration::from_millis(100))? {
+            continue;
+        }
+        match event::read()? {
+            Event::Key(k) => {
+                if let ComposerAction::Done = composer.handle_key(k) {
+                    break;
+                }
+            }
+            Event::Paste(text) => composer.paste(&text),
+            _ => {}
+        }
+    }
+    composer.apply_to(draft);
+    Ok(())
+}
 
+/// Edit `draft` in the built-in composer or `$EDITOR`, then show the review screen until it is sent, saved or discarded.
+fn compose_and_send(terminal: &mut Tui, mut draft: Draft, identities: &[SendAs]) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     let tmpdir = std::env::temp_dir();
     let pid = std::process::id();
     let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)?.as_secs();
     let path = tmpdir.join(format!("mailtui-compose-{}-{}.txt", pid, now));
 
-    let mut f = fs::File::create(&path)?;
-    writeln!(f, "To: ")?;
-    writeln!(f, "Subject: ")?;
-    writeln!(f, "")?;
-    writeln!(f, "")?;
-    f.flush()?;
-
-    let editor = std::env::var("EDITOR").unwrap_or_else(|_| String::from("nano"));
-    let status = Command::new(editor).arg(&path).status()?;
-    if !status.success() {
-        return Err(format!("editor exited with status: {}", status).into());
-    }
+    let mut review = ReviewView::new();
+    let mut needs_edit = true;
+    loop {
+        if needs_edit && use_builtin_composer() {
+            edit_in_composer(terminal, &mut draft)?;
+            needs_edit = false;
+        } else if needs_edit {
+            let from_before = draft.from.clone();
+            fs::write(&path, draft.to_compose_file())?;
+            if let Err(e) = edit_in_editor(terminal, &path) {
+                review.error = Some(e.to_string());
+            }
+            draft.apply_compose_file(&fs::read_to_string(&path)?);
+            // a From: edited to another identity brings that identity's signature along
+            if draft.from != from_before {
+                draft.signature = compose::identity_for(identities, &draft.from)
+                    .and_then(|i| i.signature.clone())
+                    .unwrap_or_default();
+            }
+            needs_edit = false;
+        }
 
-    let content = fs::read_to_string(&path)?;
-    // parse headers until blank line
-    let mut lines = content.lines();
-    let mut to = String::new();
-    let mut subject = String::new();
-    while let Some(l) = lines.next() {
-        let l = l.trim_end();
-        if l.is_empty() {
-            break;
+        terminal.draw(|f| review.render(f, &draft))?;
+        if !event::poll(std::time::Duration::from_millis(100))? {
+            continue;
         }
-        if l.to_lowercase().starts_with("to:") {
-            to = l[3..].trim().to_string();
-        } else if l.to_lowercase().starts_with("subject:") {
-            subject = l[8..].trim().to_string();
+        let Event::Key(k) = event::read()? else { continue };
+        match review.handle_key(k) {
+            ReviewAction::None => {}
+            ReviewAction::Edit => needs_edit = true,
+            ReviewAction::NextIdentity if !identities.is_empty() => {
+                let current = compose::identity_for(identities, &draft.from);
+                // drop the Reply-To that came with the previous identity
+                if current.is_some_and(|c| c.reply_to.as_deref() == Some(draft.reply_to.as_str())) {
+                    draft.reply_to.clear();
+                }
+                let next = current
+                    .and_then(|cur| identities.iter().position(|i| i.email == cur.email))
+                    .map(|i| (i + 1) % identities.len())
+                    .unwrap_or(0);
+                draft.use_identity(&identities[next]);
+            }
+            ReviewAction::NextIdentity => {}
+            ReviewAction::Attach(p) => {
+                if crate::compose::expand_home(&p).is_file() {
+                    draft.attachments.push(p);
+                    review.error = None;
+                } else {
+                    review.error = Some(format!("not a file: {}", p));
+                }
+            }
+            ReviewAction::Discard => {
+                let _ = fs::remove_file(&path);
+                return Ok(());
+            }
+            ReviewAction::SaveDraft => match save_draft(&mut draft) {
+                Ok(()) => {
+                    let _ = fs::remove_file(&path);
+                    return Ok(());
+                }
+                Err(e) => review.error = Some(format!("saving draft failed: {}", e)),
+            },
+            ReviewAction::Send => match queue_draft(draft.clone()) {
+                Ok(_) => {
+                    let _ = fs::remove_file(&path);
+                    return Ok(());
+                }
+                Err(e) => review.error = Some(format!("send failed: {}", e)),
+            },
         }
     }
+}
 
-    let body: String = lines.collect::<Vec<&str>>().join("\n");
+// problems that retrying can't fix are reported right away instead of queued.
+// Returns the outbox entry id.
+pub fn queue_draft(draft: Draft) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    if draft.to.is_empty() {
+        return Err("no To: address provided".into());
+    }
+    let raw = draft.to_message()?;
+    Ok(crate::storage::outbox::enqueue(draft, &raw)?)
+}
 
-    if to.is_empty() {
+pub fn check_draft(draft: &Draft) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    if draft.to.is_empty() {
         return Err("no To: address provided".into());
     }
+    draft.to_message()?;
+    Ok(())
+}
 
-    // Build a simple RFC2822 raw message
-    let raw = format!("To: {}\r\nSubject: {}\r\nContent-Type: text/plain; charset=utf-8\r\n\r\n{}", to, subject, body);
+// sends due outbox entries one at a time; an entry is only removed once the API accepted it
+fn spawn_outbox_worker() {
+    std::thread::spawn(|| {
+        if let Err(e) = crate::storage::outbox::recover() {
+            status::error(format!("failed to recover outbox: {}", e));
+        }
+        loop {
+            match crate::storage::outbox::claim_due() {
+                Ok(Some(entry)) => {
+                    send_entry(entry);
+                    continue;
+                }
+                Ok(None) => {}
+                Err(e) => status::error(format!("failed to read outbox: {}", e)),
+            }
+            crate::storage::outbox::wait(OUTBOX_POLL);
+        }
+    });
+}
 
-    // load token
-    let saved = crate::token_store::load_token()?;
-    let access = saved.access_token;
+fn send_entry(mut entry: crate::storage::outbox::Entry) {
+    // entries from older versions hold only the draft; build it once, `finish` keeps it for retries
+    if entry.raw.is_none()
+        && let Ok(raw) = entry.draft.to_message()
+    {
+        entry.set_message(&raw);
+    }
+    let result = match entry.message() {
+        Some(raw) => send_raw(&raw, entry.thread_id.as_deref(), entry.draft_id.as_deref(), &entry.draft),
+        None => Err("the queued message could not be built".into()),
+    };
+    let result = result.map_err(|e| e.to_string());
+    let subject = if entry.draft.subject.is_empty() { "(no subject)".to_string() } else { entry.draft.subject.clone() };
+    match &result {
+        Ok(()) => status::info(format!("sent \"{}\"", subject)),
+        Err(e) => status::error(format!("sending \"{}\" failed: {}", subject, e)),
+    }
+    if let Err(e) = crate::storage::outbox::finish(entry, result) {
+        status::error(format!("failed to update outbox: {}", e));
+    }
+}
+
+// send whatever is due in the foreground, e.g. before exiting right after composing;
+// waits (up to a minute) for entries this process's background worker is already sending.
+// Entries another mailtui is sending are left to it.
+fn flush_outbox() {
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(60);
+    while std::time::Instant::now() < deadline {
+        match crate::storage::outbox::claim_due() {
+            Ok(Some(entry)) => send_entry(entry),
+            Ok(None) if crate::storage::outbox::list().iter().any(|e| e.sending_here()) => {
+                std::thread::sleep(std::time::Duration::from_millis(200));
+            }
+            _ => break,
+        }
+    }
+}
+
+// a usable access token, refreshed if needed; never opens a browser
+pub fn access_token() -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    let (id, secret) = crate::config::get().credentials().unwrap_or_default();
+    crate::auth::mail::access_token(&id, &secret)
+}
+
+pub fn send_draft(draft: &Draft) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let raw = draft.to_message()?;
+    send_raw(&raw, draft.thread_id.as_deref(), draft.draft_id.as_deref(), draft)
+}
 
-    crate::gmail::send_mail(&access, &raw)?;
-    println!("Message sent to {}", to);
+// send `raw`, built from `draft`: through drafts.send when it replaces a saved draft
+fn send_raw(raw: &RawMessage, thread_id: Option<&str>, draft_id: Option<&str>, draft: &Draft) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let access = access_token()?;
+    match draft_id {
+        Some(id) => crate::gmail::update_draft(&access, id, raw, thread_id).and_then(|_| crate::gmail::send_draft(&access, id))?,
+        None => crate::gmail::send_mail(&access, raw, thread_id)?,
+    }
+    // the message is out; a failure to count its recipients shouldn't report a failed send
+    let recorded = crate::storage::contacts::update(|book| {
+        book.record_sent(&[&draft.to, &draft.cc, &draft.bcc]);
+        true
+    });
+    if let Err(e) = recorded {
+        log::warn!("failed to update contacts: {}", e);
+    }
     Ok(())
 }
+
+// drafts.create the first time, drafts.update afterwards
+fn save_draft(draft: &mut Draft) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let raw = draft.to_message()?;
+    let access = access_token()?;
+    match &draft.draft_id {
+        Some(id) => crate::gmail::update_draft(&access, id, &raw, draft.thread_id.as_deref()),
+        None => {
+            draft.draft_id = Some(crate::gmail::create_draft(&access, &raw, draft.thread_id.as_deref())?);
+            Ok(())
+        }
+    }
+}
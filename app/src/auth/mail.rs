@@ -11,13 +11,31 @@ use url::Url;
 use std::net::TcpListener;
 
 
-pub fn oauth_login(client_id: &str, _client_secret: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+/// Log in for the TUI: get a token (browser flow if needed) and start the background fetch loop.
+pub fn oauth_login(client_id: &str, client_secret: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    let access = login(client_id, client_secret)?;
+    spawn_fetch_loop(client_id.to_string(), client_secret.to_string());
+    Ok(access)
+}
+
+/// Run the browser flow unless the saved token is still usable; for `mailtui login`.
+pub fn login(client_id: &str, client_secret: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    obtain_token(client_id, client_secret, true)
+}
+
+/// A valid access token from the saved one, refreshing it if needed. Never opens a browser;
+/// for scripted commands.
+pub fn access_token(client_id: &str, client_secret: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    obtain_token(client_id, client_secret, false)
+}
+
+// reuse the saved token, refresh it, or (if allowed) run the browser flow
+fn obtain_token(client_id: &str, _client_secret: &str, allow_browser: bool) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
     let listener = TcpListener::bind(("127.0.0.1", 0))?;
     let port = listener.local_addr()?.port();
     drop(listener);
 
     let redirect_str = format!("http://127.0.0.1:{}/", port);
-    eprintln!("[mail] using redirect URI: {}", redirect_str);
 
     let auth_url = AuthUrl::new("https://accounts.google.com/o/oauth2/v2/auth".to_string())?;
     let token_url = TokenUrl::new("https://oauth2.googleapis.com/token".to_string())?;
@@ -25,19 +43,13 @@ pub fn oauth_login(client_id: &str, _client_secret: &str) -> Result<String, Box<
     // try to reuse saved token
     if let Ok(saved) = crate::token_store::load_token() {
         let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0);
-        if let Some(exp_at) = saved.expires_at_unix {
-            if exp_at > now + 60 {
-                // ensure background fetch runs even when returning cached token
-                spawn_fetch_loop(client_id.to_string(), _client_secret.to_string());
-                return Ok(saved.access_token);
-            }
-        } else {
-            spawn_fetch_loop(client_id.to_string(), _client_secret.to_string());
-            return Ok(saved.access_token);
+        match saved.expires_at_unix {
+            Some(exp_at) if exp_at <= now + 60 => {}
+            _ => return Ok(saved.access_token),
         }
 
         if let Some(refresh) = saved.refresh_token {
-            eprintln!("[mail] attempting refresh token flow");
+            log::debug!("attempting refresh token flow");
             let client_secret_opt = if _client_secret.is_empty() { None } else { Some(ClientSecret::new(_client_secret.to_string())) };
             let client = BasicClient::new(
                 ClientId::new(client_id.to_string()),
@@ -46,7 +58,7 @@ pub fn oauth_login(client_id: &str, _client_secret: &str) -> Result<String, Box<
                 Some(token_url.clone()),
             );
 
-                    match client.exchange_refresh_token(&RefreshToken::new(refresh)).request(http_client) {
+            match client.exchange_refresh_token(&RefreshToken::new(refresh.clone())).request(http_client) {
                 Ok(t) => {
                     let access = t.access_token().secret().to_string();
                     let expires_at_unix = t.expires_in().map(|dur| {
@@ -56,19 +68,23 @@ pub fn oauth_login(client_id: &str, _client_secret: &str) -> Result<String, Box<
                     });
                     let saved2 = SavedToken {
                         access_token: access.clone(),
-                        refresh_token: t.refresh_token().map(|r| r.secret().to_string()),
+                        // Google usually doesn't send a new refresh token; keep the old one
+                        refresh_token: t.refresh_token().map(|r| r.secret().to_string()).or(Some(refresh)),
                         expires_at_unix,
                     };
                     let _ = save_token(&saved2);
-                    // start periodic fetch loop and return access
-                    spawn_fetch_loop(client_id.to_string(), _client_secret.to_string());
                     return Ok(access);
                 }
-                Err(e) => eprintln!("[mail] refresh failed: {:#?}", e),
+                Err(e) => log::warn!("token refresh failed: {:?}", e),
             }
         }
     }
 
+    if !allow_browser {
+        return Err("not logged in (or the session expired); run `mailtui login`".into());
+    }
+    log::debug!("using redirect URI: {}", redirect_str);
+
     let redirect = RedirectUrl::new(redirect_str.clone())?;
 
     let client_secret_opt = if _client_secret.is_empty() { None } else { Some(ClientSecret::new(_client_secret.to_string())) };
@@ -84,7 +100,7 @@ pub fn oauth_login(client_id: &str, _client_secret: &str) -> Result<String, Box<
     let (pkce_challenge, pkce_verifier) = PkceCodeChallenge::new_random_sha256();
 
     let (url, _state) = client
-        .authorize_url(|| CsrfToken::new_random())
+        .authorize_url(CsrfToken::new_random)
         .add_scope(Scope::new("https://mail.google.com/".to_string()))
         .add_extra_param("access_type", "offline")
         .add_extra_param("prompt", "consent")
@@ -102,7 +118,7 @@ pub fn oauth_login(client_id: &str, _client_secret: &str) -> Result<String, Box<
                 break;
             }
             Err(e) => {
-                eprintln!("[mail] bind attempt {} failed: {}", attempt + 1, e);
+                log::debug!("bind attempt {} failed: {}", attempt + 1, e);
                 std::thread::sleep(std::time::Duration::from_millis(100));
             }
         }
@@ -112,13 +128,13 @@ pub fn oauth_login(client_id: &str, _client_secret: &str) -> Result<String, Box<
     println!("Listening on http://{}", bind_addr);
     println!("Open this URL to continue:\n{}", url.as_str());
     if let Some(pair) = url.query_pairs().find(|(k, _)| k == "redirect_uri") {
-        eprintln!("[mail] redirect_uri param in auth URL: {}", pair.1);
+        log::debug!("redirect_uri param in auth URL: {}", pair.1);
     }
     let _ = open::that(url.as_str());
 
     let request = server.recv()?;
     let full_url = format!("http://127.0.0.1:{}{}", port, request.url());
-    eprintln!("[mail] received callback URL: {}", full_url);
+    log::debug!("received callback URL: {}", full_url);
     let parsed = Url::parse(&full_url)?;
     let code_pair = parsed
         .query_pairs()
@@ -176,7 +192,7 @@ pub fn oauth_login(client_id: &str, _client_secret: &str) -> Result<String, Box<
     {
         Ok(t) => t,
         Err(e) => {
-            eprintln!("[mail] token exchange failed: {:#?}", e);
+            log::error!("token exchange failed: {:?}", e);
             return Err(format!("token exchange failed: {}", e).into());
         }
     };
@@ -197,18 +213,18 @@ pub fn oauth_login(client_id: &str, _client_secret: &str) -> Result<String, Box<
     };
     let _ = save_token(&saved);
 
-    spawn_fetch_loop(client_id.to_string(), _client_secret.to_string());
-
     Ok(access)
 }
 
 fn spawn_fetch_loop(client_id: String, client_secret: String) {
     std::thread::spawn(move || {
-        let interval_secs: u64 = std::env::var("MAIL_FETCH_INTERVAL_SECONDS").ok().and_then(|s| s.parse().ok()).unwrap_or(5);
+        let sync = &crate::config::get().sync;
         let auth_url = AuthUrl::new("https://accounts.google.com/o/oauth2/v2/auth".to_string()).ok();
         let token_url = TokenUrl::new("https://oauth2.googleapis.com/token".to_string()).ok();
+        let mut label_names = crate::gmail::LabelNames::new();
 
         loop {
+            crate::ui::status::sync_started();
             // load saved token
             match load_token() {
                 Ok(saved) => {
@@ -217,53 +233,72 @@ fn spawn_fetch_loop(client_id: String, client_secret: String) {
                     // refresh if expired or about to expire
                     let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0);
                     let need_refresh = saved.expires_at_unix.map(|e| e <= now + 60).unwrap_or(false);
-                    if need_refresh {
-                        if let Some(ref_token) = saved.refresh_token.clone() {
-                            if let (Some(aurl), Some(turl)) = (auth_url.clone(), token_url.clone()) {
-                                let client_secret_opt = if client_secret.is_empty() { None } else { Some(ClientSecret::new(client_secret.clone())) };
-                                let client = BasicClient::new(
-                                    ClientId::new(client_id.clone()),
-                                    client_secret_opt,
-                                    aurl,
-                                    Some(turl),
-                                );
-
-                                match client.exchange_refresh_token(&RefreshToken::new(ref_token)).request(http_client) {
-                                    Ok(t) => {
-                                        access_tok = t.access_token().secret().to_string();
-                                        let expires_at_unix = t.expires_in().map(|dur| {
-                                            let now = std::time::SystemTime::now();
-                                            let then = now + dur;
-                                            then.duration_since(std::time::UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0)
-                                        });
-                                        let saved2 = SavedToken {
-                                            access_token: access_tok.clone(),
-                                            refresh_token: t.refresh_token().map(|r| r.secret().to_string()),
-                                            expires_at_unix,
-                                        };
-                                        let _ = save_token(&saved2);
-                                    }
-                                    Err(e) => eprintln!("[mail-bg] refresh failed: {:#?}", e),
-                                }
+                    if need_refresh
+                        && let Some(ref_token) = saved.refresh_token.clone()
+                        && let (Some(aurl), Some(turl)) = (auth_url.clone(), token_url.clone())
+                    {
+                        let client_secret_opt = if client_secret.is_empty() { None } else { Some(ClientSecret::new(client_secret.clone())) };
+                        let client = BasicClient::new(
+                            ClientId::new(client_id.clone()),
+                            client_secret_opt,
+                            aurl,
+                            Some(turl),
+                        );
+
+                        match client.exchange_refresh_token(&RefreshToken::new(ref_token)).request(http_client) {
+                            Ok(t) => {
+                                access_tok = t.access_token().secret().to_string();
+                                let expires_at_unix = t.expires_in().map(|dur| {
+                                    let now = std::time::SystemTime::now();
+                                    let then = now + dur;
+                                    then.duration_since(std::time::UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0)
+                                });
+                                let saved2 = SavedToken {
+                                    access_token: access_tok.clone(),
+                                    refresh_token: t.refresh_token().map(|r| r.secret().to_string()).or(saved.refresh_token.clone()),
+                                    expires_at_unix,
+                                };
+                                let _ = save_token(&saved2);
                             }
+                            Err(e) => log::warn!("background token refresh failed: {:?}", e),
                         }
                     }
 
                     // fetch mails
-                    match crate::gmail::fetch_latest(&access_tok, 10) {
-                        Ok(msgs) => crate::ui::set_messages(msgs),
-                        Err(e) => eprintln!("failed to fetch gmail messages (bg): {}", e),
+                    match crate::gmail::fetch_latest(&access_tok, sync.page_size) {
+                        Ok(msgs) => {
+                            if let Err(e) = crate::storage::contacts::update(|book| book.record_messages(&msgs)) {
+                                log::warn!("failed to update contacts: {}", e);
+                            }
+                            // merge into the offline cache and show the cached inbox, which
+                            // keeps older messages beyond this fetch
+                            let merged = crate::storage::cache::sync_inbox(&msgs)
+                                .and_then(|_| crate::storage::cache::load_inbox(crate::storage::cache::INBOX_LIMIT));
+                            let inbox = match merged {
+                                Ok(cached) if !cached.is_empty() => cached,
+                                Ok(_) => msgs,
+                                Err(e) => {
+                                    log::warn!("failed to update message cache: {}", e);
+                                    msgs
+                                }
+                            };
+                            // the first sync, or a label created since the last one, leaves ids without a name
+                            if crate::gmail::has_unknown_labels(&inbox, &label_names) {
+                                match crate::gmail::fetch_label_names(&access_tok) {
+                                    Ok(names) => label_names = names,
+                                    Err(e) => log::warn!("could not load label names: {}", e),
+                                }
+                            }
+                            crate::events::send(crate::events::AppEvent::Inbox { messages: inbox, labels: label_names.clone() });
+                            crate::ui::status::sync_finished(Ok(()));
+                        }
+                        Err(e) => crate::ui::status::sync_finished(Err(e.to_string())),
                     }
                 }
-                Err(e) => eprintln!("[mail-bg] load_token failed: {}", e),
+                Err(e) => crate::ui::status::sync_finished(Err(format!("can't load the saved token: {}", e))),
             }
 
-            std::thread::sleep(std::time::Duration::from_secs(interval_secs));
+            std::thread::sleep(std::time::Duration::from_secs(sync.interval_seconds));
         }
     });
 }
-
-/// sample stuff - will use oauth token (not implemented)
-pub fn connect_oauth(_email: &str, _access_token: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    Err("not implemented: XOAUTH2 authenticate".into())
-}
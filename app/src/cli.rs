@@ -0,0 +1,305 @@
+use clap::{Parser, Subcommand};
+use serde_json::json;
+use std::error::Error;
+use std::io::Read;
+use std::path::PathBuf;
+
+use crate::app::access_token;
+use crate::compose::{self, Draft};
+use crate::config;
+use crate::gmail::SimpleMail;
+use crate::storage::{cache, index};
+
+type CliResult = Result<(), Box<dyn Error + Send + Sync>>;
+
+/// Terminal mail client. Without a subcommand, starts the interactive UI.
+#[derive(Parser, Debug)]
+#[command(name = "mailtui", version)]
+pub struct Cli {
+    /// Read this config file instead of the default config.toml
+    #[arg(long, global = true, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+    /// Use this configured account instead of the default one
+    #[arg(long, global = true, value_name = "NAME")]
+    pub account: Option<String>,
+    /// Don't log in or sync; work from the local cache
+    #[arg(long, global = true)]
+    pub offline: bool,
+    /// Write the diagnostic log here instead of mailtui.log in the state dir [default: log.file]
+    #[arg(long, global = true, value_name = "PATH")]
+    pub log_file: Option<PathBuf>,
+    /// How much to log: off, error, warn, info, debug or trace [default: log.level]
+    #[arg(long, global = true, value_name = "LEVEL")]
+    pub log_level: Option<log::LevelFilter>,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Run the interactive client (the default)
+    Tui,
+    /// Open the composer, prefilled from a mailto: URI if given; exits after sending.
+    /// A bare mailto: argument (`mailtui mailto:...`) does the same.
+    Compose {
+        /// e.g. mailto:alice@example.com?subject=Hello&body=Hi%20there
+        uri: Option<String>,
+    },
+    /// Log in through the browser and save the token
+    Login,
+    /// Forget the saved token
+    Logout,
+    /// List configured accounts as JSON
+    Accounts,
+    /// Print inbox messages (or search results) as JSON, newest first
+    List {
+        /// Search query instead of the inbox; Gmail syntax online, the local index offline
+        #[arg(short, long)]
+        query: Option<String>,
+        /// How many messages to print [default: sync.page_size]
+        #[arg(short = 'n', long)]
+        limit: Option<usize>,
+    },
+    /// Print one message, including its body, as JSON
+    Read {
+        /// Gmail message id, as printed by `list`
+        id: String,
+    },
+    /// Send a message whose body is read from stdin; prints the outcome as JSON.
+    /// With --offline the message goes to the outbox instead.
+    Send {
+        #[arg(long, required = true)]
+        to: Vec<String>,
+        #[arg(long)]
+        cc: Vec<String>,
+        #[arg(long)]
+        bcc: Vec<String>,
+        #[arg(short, long, default_value = "")]
+        subject: String,
+        /// Sender address; defaults to the account's default identity
+        #[arg(long)]
+        from: Option<String>,
+        /// File to attach; repeat for several
+        #[arg(short, long, value_name = "PATH")]
+        attach: Vec<String>,
+    },
+    /// Send a complete RFC 5322 message from stdin, sendmail(8)-style, for cron jobs and
+    /// git send-email. Exits with sysexits codes; also run when invoked as `sendmail`.
+    Sendmail(crate::sendmail::SendmailArgs),
+}
+
+// global options that take a value, for `expand_mailto`
+const VALUE_OPTIONS: [&str; 4] = ["--config", "--account", "--log-file", "--log-level"];
+
+/// `mailtui [options] mailto:...`, as desktop URL handlers call it, means `compose`. Only a
+/// URI in the subcommand position counts, so `list --query mailto:bob@x` stays a query.
+pub fn expand_mailto(args: &mut Vec<std::ffi::OsString>) {
+    let mut i = 1;
+    while let Some(arg) = args.get(i).map(|a| a.to_string_lossy()) {
+        if VALUE_OPTIONS.contains(&arg.as_ref()) {
+            i += 2;
+        } else if arg.starts_with("--") && arg.len() > 2 {
+            // a flag, or an option written as --name=value
+            i += 1;
+        } else {
+            if arg.get(..7).is_some_and(|s| s.eq_ignore_ascii_case("mailto:")) {
+                args.insert(i, "compose".into());
+            }
+            return;
+        }
+    }
+}
+
+pub fn run(cli: Cli) -> CliResult {
+    match cli.command.unwrap_or(Command::Tui) {
+        Command::Tui => Ok(crate::app::run(cli.offline, None)?),
+        Command::Compose { uri } => {
+            let draft = match uri {
+                Some(u) => compose::mailto::parse(&u)?,
+                None => Draft::default(),
+            };
+            Ok(crate::app::run(cli.offline, Some(draft))?)
+        }
+        Command::Login => login(cli.offline),
+        Command::Logout => {
+            let removed = crate::token_store::delete_token()?;
+            print_json(&json!({ "account": account_name(), "logged_out": removed }))
+        }
+        Command::Accounts => accounts(),
+        Command::List { query, limit } => list(query, limit.unwrap_or(config::get().sync.page_size), cli.offline),
+        Command::Read { id } => read(&id, cli.offline),
+        Command::Send { to, cc, bcc, subject, from, attach } => {
+            let mut body = String::new();
+            std::io::stdin().read_to_string(&mut body)?;
+            let draft = Draft {
+                to: to.join(", "),
+                cc: cc.join(", "),
+                bcc: bcc.join(", "),
+                subject,
+                body,
+                attachments: attach,
+                ..Draft::default()
+            };
+            send(draft, from, cli.offline)
+        }
+        Command::Sendmail(args) => std::process::exit(crate::sendmail::run(args, cli.offline)),
+    }
+}
+
+fn print_json(value: &impl serde::Serialize) -> CliResult {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+fn account_name() -> Option<String> {
+    config::get().account().map(|a| a.name.clone())
+}
+
+fn login(offline: bool) -> CliResult {
+    if offline {
+        return Err("can't log in with --offline".into());
+    }
+    let (id, secret) = config::get()
+        .credentials()
+        .ok_or("no OAuth client configured; set client_id/client_secret for the account or MAIL_OAUTH_CLIENT_ID/MAIL_OAUTH_CLIENT_SECRET")?;
+    let token = crate::auth::mail::login(&id, &secret)?;
+    let email = crate::gmail::fetch_profile_email(&token).ok();
+    print_json(&json!({ "account": account_name(), "logged_in": true, "email": email }))
+}
+
+fn accounts() -> CliResult {
+    let cfg = config::get();
+    let current = cfg.account().map(|a| a.name.as_str());
+    let out: Vec<_> = cfg
+        .accounts
+        .iter()
+        .map(|a| {
+            let dir = crate::storage::account_dir_of(Some(a));
+            json!({
+                "name": a.name,
+                "provider": a.provider.name(),
+                "email": a.email,
+                "default": a.default,
+                "selected": Some(a.name.as_str()) == current,
+                "logged_in": crate::token_store::token_file_in(&dir).exists(),
+            })
+        })
+        .collect();
+    print_json(&out)
+}
+
+// messages without their bodies; `read` prints those
+fn summaries(msgs: Vec<SimpleMail>) -> Vec<SimpleMail> {
+    msgs.into_iter().map(|m| SimpleMail { body: None, ..m }).collect()
+}
+
+// label ids to names for output; offline, or when the names can't be fetched, ids stay
+fn name_labels(mut msgs: Vec<SimpleMail>, offline: bool) -> Vec<SimpleMail> {
+    let names = if offline {
+        Default::default()
+    } else {
+        access_token().and_then(|token| crate::gmail::fetch_label_names(&token)).unwrap_or_default()
+    };
+    for m in &mut msgs {
+        m.labels = m.labels.iter().map(|l| crate::gmail::label_name(l, &names)).collect();
+    }
+    msgs
+}
+
+fn list(query: Option<String>, limit: usize, offline: bool) -> CliResult {
+    let msgs = match (query, offline) {
+        (Some(q), true) => index::search(&q, limit)?,
+        (None, true) => cache::load_inbox(limit)?,
+        (Some(q), false) => {
+            let msgs = crate::gmail::search(&access_token()?, &q, limit)?;
+            let _ = cache::store_messages(&msgs, false);
+            msgs
+        }
+        (None, false) => {
+            let msgs = crate::gmail::fetch_latest(&access_token()?, limit)?;
+            let _ = cache::sync_inbox(&msgs);
+            msgs
+        }
+    };
+    print_json(&summaries(name_labels(msgs, offline)))
+}
+
+fn read(id: &str, offline: bool) -> CliResult {
+    let fetched = if offline {
+        Err("offline".into())
+    } else {
+        access_token().and_then(|token| crate::gmail::fetch_message(&token, id))
+    };
+    let msg = match fetched {
+        Ok(m) => {
+            let _ = cache::store_messages(std::slice::from_ref(&m), false);
+            m
+        }
+        // fall back to the cache when Gmail can't be reached
+        Err(e) => cache::load_message(id)?.ok_or_else(|| if offline { format!("message {} is not cached", id) } else { e.to_string() })?,
+    };
+    print_json(&name_labels(vec![msg], offline)[0])
+}
+
+fn send(mut draft: Draft, from: Option<String>, offline: bool) -> CliResult {
+    // refresh the token first; identities and sending read the saved one
+    let ids = if offline {
+        crate::app::identities(true)
+    } else {
+        access_token()?;
+        crate::app::fetch_identities()
+    };
+    match from {
+        Some(f) => match compose::identity_for(&ids, &f) {
+            Some(id) => draft.use_identity(id),
+            None => draft.from = f,
+        },
+        None => {
+            if let Some(id) = compose::pick_identity(&ids, None) {
+                draft.use_identity(id);
+            }
+        }
+    }
+
+    if offline {
+        let id = crate::app::queue_draft(draft)?;
+        return print_json(&json!({ "queued": id }));
+    }
+    crate::app::check_draft(&draft)?;
+    crate::app::send_draft(&draft)?;
+    print_json(&json!({ "sent": true, "from": draft.from, "to": draft.to, "subject": draft.subject }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expanded(args: &[&str]) -> Vec<String> {
+        let mut args = args.iter().map(std::ffi::OsString::from).collect();
+        expand_mailto(&mut args);
+        args.into_iter().map(|a| a.to_string_lossy().into_owned()).collect()
+    }
+
+    #[test]
+    fn bare_mailto_means_compose() {
+        assert_eq!(expanded(&["mailtui", "mailto:bob@x"]), ["mailtui", "compose", "mailto:bob@x"]);
+        assert_eq!(expanded(&["mailtui", "MAILTO:bob@x"]), ["mailtui", "compose", "MAILTO:bob@x"]);
+        assert_eq!(
+            expanded(&["mailtui", "--offline", "--account", "work", "--log-level=debug", "mailto:bob@x"]),
+            ["mailtui", "--offline", "--account", "work", "--log-level=debug", "compose", "mailto:bob@x"]
+        );
+        assert!(Cli::try_parse_from(expanded(&["mailtui", "--offline", "mailto:bob@x"])).is_ok());
+    }
+
+    #[test]
+    fn mailto_elsewhere_is_left_alone() {
+        for args in [
+            &["mailtui", "compose", "mailto:bob@x"][..],
+            &["mailtui", "list", "--query", "mailto:bob@x"],
+            &["mailtui", "--account", "mailto:bob@x", "list"],
+        ] {
+            assert_eq!(expanded(args), args);
+        }
+        assert!(Cli::try_parse_from(expanded(&["mailtui", "list", "--query", "mailto:bob@x"])).is_ok());
+    }
+}
@@ -0,0 +1,482 @@
+use base64::Engine;
+use std::hash::{BuildHasher, Hasher};
+use std::io;
+use std::path::Path;
+
+use super::split_addresses;
+
+// RFC 5322 recommends lines of at most 78 characters; quoted-printable allows 76
+const MAX_HEADER_LINE: usize = 78;
+const MAX_QP_LINE: usize = 76;
+// "=?UTF-8?B?" + "?=" leave 63 base64 chars (45 bytes) for a 75-char encoded word
+const MAX_WORD_BYTES: usize = 45;
+
+/// A file attached to an outgoing message.
+#[derive(Debug, Clone)]
+pub struct Attachment {
+    pub filename: String,
+    pub content_type: String,
+    pub data: Vec<u8>,
+}
+
+impl Attachment {
+    /// Read `path` from disk, guessing the MIME type from its extension.
+    pub fn from_path(path: &Path) -> io::Result<Self> {
+        let data = std::fs::read(path)?;
+        let filename = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "attachment".into());
+        let content_type = mime_guess::from_path(path).first_or_octet_stream().essence_str().to_string();
+        Ok(Attachment { filename, content_type, data })
+    }
+}
+
+/// A finished RFC 5322 message, CRLF line endings, ready for the Gmail API.
+#[derive(Debug, Clone)]
+pub struct RawMessage(Vec<u8>);
+
+impl From<Vec<u8>> for RawMessage {
+    /// Wrap an already complete message, e.g. one piped in by `mailtui sendmail`.
+    fn from(bytes: Vec<u8>) -> Self {
+        RawMessage(bytes)
+    }
+}
+
+impl RawMessage {
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+/// Builds standards-compliant outgoing mail: Date, Message-ID and MIME-Version are always set,
+/// non-ASCII header text is RFC 2047 encoded, long headers are folded and the body is
+/// quoted-printable encoded when it isn't plain short-lined ASCII. With attachments the
+/// message becomes multipart/mixed and each file is base64 encoded.
+#[derive(Debug, Clone, Default)]
+pub struct MessageBuilder {
+    from: Option<String>,
+    to: Vec<String>,
+    cc: Vec<String>,
+    bcc: Vec<String>,
+    reply_to: Option<String>,
+    subject: String,
+    in_reply_to: Option<String>,
+    references: Option<String>,
+    body: String,
+    attachments: Vec<Attachment>,
+}
+
+impl MessageBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from(mut self, addr: &str) -> Self {
+        self.from = Some(addr.trim().to_string()).filter(|a| !a.is_empty());
+        self
+    }
+
+    /// Comma-separated address list; may be called repeatedly.
+    pub fn to(mut self, list: &str) -> Self {
+        self.to.extend(split_addresses(list));
+        self
+    }
+
+    pub fn cc(mut self, list: &str) -> Self {
+        self.cc.extend(split_addresses(list));
+        self
+    }
+
+    pub fn bcc(mut self, list: &str) -> Self {
+        self.bcc.extend(split_addresses(list));
+        self
+    }
+
+    pub fn reply_to(mut self, addr: &str) -> Self {
+        self.reply_to = Some(addr.trim().to_string()).filter(|a| !a.is_empty());
+        self
+    }
+
+    pub fn subject(mut self, subject: &str) -> Self {
+        self.subject = subject.trim().to_string();
+        self
+    }
+
+    pub fn in_reply_to(mut self, message_id: &str) -> Self {
+        self.in_reply_to = Some(message_id.to_string());
+        self
+    }
+
+    pub fn references(mut self, ids: &str) -> Self {
+        self.references = Some(ids.to_string());
+        self
+    }
+
+    pub fn body(mut self, body: &str) -> Self {
+        self.body = body.to_string();
+        self
+    }
+
+    pub fn attach(mut self, attachment: Attachment) -> Self {
+        self.attachments.push(attachment);
+        self
+    }
+
+    pub fn build(&self) -> RawMessage {
+        let mut out = String::new();
+
+        out.push_str(&fold("Date", &chrono::Local::now().to_rfc2822()));
+        out.push_str(&fold("Message-ID", &new_message_id(self.from.as_deref())));
+        if let Some(from) = &self.from {
+            out.push_str(&fold("From", &encode_address_list(std::slice::from_ref(from))));
+        }
+        if !self.to.is_empty() {
+            out.push_str(&fold("To", &encode_address_list(&self.to)));
+        }
+        if !self.cc.is_empty() {
+            out.push_str(&fold("Cc", &encode_address_list(&self.cc)));
+        }
+        // Gmail reads Bcc from the raw message and strips it before delivery
+        if !self.bcc.is_empty() {
+            out.push_str(&fold("Bcc", &encode_address_list(&self.bcc)));
+        }
+        if let Some(reply_to) = &self.reply_to {
+            out.push_str(&fold("Reply-To", &encode_address_list(std::slice::from_ref(reply_to))));
+        }
+        out.push_str(&fold("Subject", &encode_words(&self.subject)));
+        if let Some(id) = &self.in_reply_to {
+            out.push_str(&fold("In-Reply-To", id));
+        }
+        if let Some(refs) = &self.references {
+            out.push_str(&fold("References", refs));
+        }
+        out.push_str("MIME-Version: 1.0\r\n");
+
+        let (encoding, body) = encode_text_body(&self.body);
+        if self.attachments.is_empty() {
+            out.push_str("Content-Type: text/plain; charset=utf-8\r\n");
+            out.push_str(&format!("Content-Transfer-Encoding: {}\r\n", encoding));
+            out.push_str("\r\n");
+            out.push_str(&body);
+            return RawMessage(out.into_bytes());
+        }
+
+        let boundary = new_boundary();
+        out.push_str(&format!("Content-Type: multipart/mixed; boundary=\"{}\"\r\n", boundary));
+        out.push_str("\r\n");
+        out.push_str("This is a multi-part message in MIME format.\r\n");
+
+        out.push_str(&format!("--{}\r\n", boundary));
+        out.push_str("Content-Type: text/plain; charset=utf-8\r\n");
+        out.push_str(&format!("Content-Transfer-Encoding: {}\r\n", encoding));
+        out.push_str("\r\n");
+        out.push_str(&body);
+
+        for a in &self.attachments {
+            out.push_str(&format!("--{}\r\n", boundary));
+            out.push_str(&fold("Content-Type", &format!("{}; {}", a.content_type, filename_param("name", &a.filename))));
+            out.push_str(&fold("Content-Disposition", &format!("attachment; {}", filename_param("filename", &a.filename))));
+            out.push_str("Content-Transfer-Encoding: base64\r\n");
+            out.push_str("\r\n");
+            out.push_str(&base64_lines(&a.data));
+        }
+        out.push_str(&format!("--{}--\r\n", boundary));
+
+        RawMessage(out.into_bytes())
+    }
+}
+
+pub(crate) fn random_hex() -> u64 {
+    // RandomState is seeded per process from the OS; good enough for uniqueness here
+    let mut h = std::collections::hash_map::RandomState::new().build_hasher();
+    h.write_i64(chrono::Utc::now().timestamp_nanos_opt().unwrap_or(0));
+    h.finish()
+}
+
+fn new_boundary() -> String {
+    format!("=_mailtui_{:016x}{:016x}", random_hex(), random_hex())
+}
+
+// RFC 2231 extended parameter for non-ASCII names, quoted string otherwise
+fn filename_param(param: &str, filename: &str) -> String {
+    if needs_encoding(filename) {
+        let encoded: String = filename
+            .bytes()
+            .map(|b| match b {
+                b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'.' | b'-' | b'_' => (b as char).to_string(),
+                _ => format!("%{:02X}", b),
+            })
+            .collect();
+        format!("{}*=UTF-8''{}", param, encoded)
+    } else {
+        format!("{}=\"{}\"", param, filename.replace('"', "\\\""))
+    }
+}
+
+// base64 wrapped at 76 characters per line
+fn base64_lines(data: &[u8]) -> String {
+    let encoded = base64::engine::general_purpose::STANDARD.encode(data);
+    let mut out = String::with_capacity(encoded.len() + encoded.len() / 38);
+    for chunk in encoded.as_bytes().chunks(MAX_QP_LINE) {
+        out.push_str(std::str::from_utf8(chunk).unwrap_or_default());
+        out.push_str("\r\n");
+    }
+    out
+}
+
+// <unix-millis.pid.random@domain-of-sender>
+fn new_message_id(from: Option<&str>) -> String {
+    let domain = from
+        .map(super::addr_spec)
+        .and_then(|a| a.rsplit_once('@').map(|(_, d)| d.to_string()))
+        .filter(|d| !d.is_empty())
+        .unwrap_or_else(|| "mailtui.local".to_string());
+    let millis = chrono::Utc::now().timestamp_millis();
+    format!("<{}.{}.{:016x}@{}>", millis, std::process::id(), random_hex(), domain)
+}
+
+/// Fold `Name: value` so no line exceeds 78 characters where the value allows it, CRLF
+/// terminated. Folds go in front of whitespace already in the value, which is kept as is;
+/// line breaks inside the value become spaces.
+pub fn fold(name: &str, value: &str) -> String {
+    // a bare line break would end the header early
+    let value = value.trim().replace(['\r', '\n'], " ");
+    let mut out = format!("{}:", name);
+    let mut line_len = out.len();
+    let mut rest = value.as_str();
+    let mut first = true;
+    while !rest.is_empty() {
+        // the next word with the whitespace in front of it
+        let ws = rest.len() - rest.trim_start().len();
+        let end = rest[ws..].find(char::is_whitespace).map_or(rest.len(), |i| ws + i);
+        let (token, tail) = rest.split_at(end);
+        if first {
+            out.push(' ');
+            line_len += 1;
+            first = false;
+        } else if line_len + token.len() > MAX_HEADER_LINE && line_len > name.len() + 1 {
+            out.push_str("\r\n");
+            line_len = 0;
+        }
+        out.push_str(token);
+        line_len += token.len();
+        rest = tail;
+    }
+    out.push_str("\r\n");
+    out
+}
+
+fn needs_encoding(s: &str) -> bool {
+    s.chars().any(|c| !c.is_ascii() || c.is_ascii_control())
+}
+
+/// RFC 2047 B-encode `text` if it contains non-ASCII characters, split into
+/// space-separated encoded words that never break a UTF-8 sequence.
+pub fn encode_words(text: &str) -> String {
+    if !needs_encoding(text) {
+        return text.to_string();
+    }
+    let mut words = Vec::new();
+    let mut chunk = String::new();
+    for c in text.chars() {
+        if chunk.len() + c.len_utf8() > MAX_WORD_BYTES {
+            words.push(encoded_word(&chunk));
+            chunk.clear();
+        }
+        chunk.push(c);
+    }
+    if !chunk.is_empty() {
+        words.push(encoded_word(&chunk));
+    }
+    words.join(" ")
+}
+
+fn encoded_word(s: &str) -> String {
+    format!("=?UTF-8?B?{}?=", base64::engine::general_purpose::STANDARD.encode(s.as_bytes()))
+}
+
+// only display names get encoded; the addr-spec must stay ASCII
+fn encode_address_list(addrs: &[String]) -> String {
+    addrs
+        .iter()
+        .map(|a| match a.rfind('<') {
+            Some(i) if i > 0 => {
+                let name = a[..i].trim().trim_matches('"');
+                let spec = &a[i..];
+                if needs_encoding(name) {
+                    format!("{} {}", encode_words(name), spec)
+                } else if name.contains([',', ';', ':', '@', '.', '(', ')']) {
+                    format!("\"{}\" {}", name.replace('"', "\\\""), spec)
+                } else {
+                    format!("{} {}", name, spec)
+                }
+            }
+            _ => a.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Pick a transfer encoding for a text body and return it with the encoded, CRLF-terminated body.
+fn encode_text_body(body: &str) -> (&'static str, String) {
+    let lines: Vec<&str> = body.lines().collect();
+    let plain = body.is_ascii() && lines.iter().all(|l| l.len() <= MAX_QP_LINE && !l.ends_with([' ', '\t']));
+    if plain {
+        let mut out = lines.join("\r\n");
+        out.push_str("\r\n");
+        ("7bit", out)
+    } else {
+        let mut out = String::new();
+        for l in lines {
+            out.push_str(&quoted_printable_line(l));
+            out.push_str("\r\n");
+        }
+        ("quoted-printable", out)
+    }
+}
+
+// encode one line of text, inserting soft breaks ("=" CRLF) to stay within 76 characters
+fn quoted_printable_line(line: &str) -> String {
+    let bytes = line.as_bytes();
+    let mut out = String::new();
+    let mut line_len = 0;
+    for (i, &b) in bytes.iter().enumerate() {
+        let last = i + 1 == bytes.len();
+        let token = match b {
+            // trailing whitespace would be stripped in transit
+            b' ' | b'\t' if last => format!("={:02X}", b),
+            b' ' | b'\t' => (b as char).to_string(),
+            b'=' => "=3D".to_string(),
+            33..=126 => (b as char).to_string(),
+            _ => format!("={:02X}", b),
+        };
+        // leave room for the soft-break "=" unless this is the final token
+        let limit = if last { MAX_QP_LINE } else { MAX_QP_LINE - 1 };
+        if line_len + token.len() > limit {
+            out.push_str("=\r\n");
+            line_len = 0;
+        }
+        line_len += token.len();
+        out.push_str(&token);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode_word(word: &str) -> Vec<u8> {
+        let b64 = word.strip_prefix("=?UTF-8?B?").and_then(|w| w.strip_suffix("?=")).unwrap();
+        base64::engine::general_purpose::STANDARD.decode(b64).unwrap()
+    }
+
+    #[test]
+    fn encoded_words_stay_short_and_keep_characters_whole() {
+        assert_eq!(encode_words("plain subject"), "plain subject");
+        let text = "Grüße aus Köln 🎉 ".repeat(6);
+        let encoded = encode_words(&text);
+        let mut decoded = String::new();
+        for word in encoded.split(' ') {
+            assert!(word.len() <= 75, "{}", word);
+            // every word decodes to valid UTF-8 on its own
+            decoded.push_str(&String::from_utf8(decode_word(word)).unwrap());
+        }
+        assert_eq!(decoded, text);
+    }
+
+    #[test]
+    fn fold_breaks_before_existing_whitespace() {
+        let value = "word ".repeat(40);
+        let folded = fold("Subject", &value);
+        assert!(folded.ends_with("\r\n"));
+        let lines: Vec<&str> = folded.trim_end_matches("\r\n").split("\r\n").collect();
+        assert!(lines.len() > 1);
+        for (i, line) in lines.iter().enumerate() {
+            assert!(line.len() <= 78, "{:?}", line);
+            assert_eq!(i > 0, line.starts_with(' '), "{:?}", line);
+        }
+        // unfolding gives back the original value
+        assert_eq!(folded.replace("\r\n", ""), format!("Subject: {}", value.trim()));
+    }
+
+    #[test]
+    fn fold_keeps_runs_of_whitespace() {
+        assert_eq!(fold("X-Test", "a  b\tc"), "X-Test: a  b\tc\r\n");
+        let value = format!("{}\t\t{}", "a".repeat(60), "b".repeat(30));
+        assert_eq!(fold("X-Test", &value), format!("X-Test: {}\r\n\t\t{}\r\n", "a".repeat(60), "b".repeat(30)));
+        assert_eq!(fold("Subject", ""), "Subject:\r\n");
+    }
+
+    #[test]
+    fn fold_does_not_let_values_start_new_headers() {
+        assert_eq!(fold("Subject", "hi\r\nBcc: x@example.com"), "Subject: hi  Bcc: x@example.com\r\n");
+    }
+
+    #[test]
+    fn quoted_printable_soft_breaks_and_trailing_whitespace() {
+        let (encoding, body) = encode_text_body("short line\nsecond");
+        assert_eq!((encoding, body.as_str()), ("7bit", "short line\r\nsecond\r\n"));
+
+        let (encoding, body) = encode_text_body("trailing space \nx=1");
+        assert_eq!(encoding, "quoted-printable");
+        assert_eq!(body, "trailing space=20\r\nx=3D1\r\n");
+
+        let long = "é".repeat(60);
+        let encoded = quoted_printable_line(&long);
+        let lines: Vec<&str> = encoded.split("\r\n").collect();
+        assert!(lines.len() > 1);
+        for line in &lines[..lines.len() - 1] {
+            assert!(line.len() <= 76 && line.ends_with('='), "{:?}", line);
+        }
+        // soft breaks never split an escape
+        assert_eq!(encoded.replace("=\r\n", ""), "=C3=A9".repeat(60));
+    }
+
+    #[test]
+    fn address_lists_encode_only_display_names() {
+        let list = encode_address_list(&["Jörg Müller <jm@example.com>".to_string(), "Doe, John <jd@example.com>".to_string(), "plain@example.com".to_string()]);
+        let (first, rest) = list.split_once(", ").unwrap();
+        let (word, spec) = first.rsplit_once(' ').unwrap();
+        assert_eq!(decode_word(word), "Jörg Müller".as_bytes());
+        assert_eq!(spec, "<jm@example.com>");
+        assert_eq!(rest, "\"Doe, John\" <jd@example.com>, plain@example.com");
+    }
+
+    #[test]
+    fn filename_params_use_rfc2231_for_non_ascii() {
+        assert_eq!(filename_param("filename", "résumé v2.pdf"), "filename*=UTF-8''r%C3%A9sum%C3%A9%20v2.pdf");
+        assert_eq!(filename_param("name", "say \"hi\".txt"), "name=\"say \\\"hi\\\".txt\"");
+    }
+
+    #[test]
+    fn attachments_make_a_multipart_message() {
+        let data: Vec<u8> = (0..=255).cycle().take(1000).collect();
+        let raw = MessageBuilder::new()
+            .to("a@example.com")
+            .subject("files")
+            .body("see attached")
+            .attach(Attachment { filename: "data.bin".into(), content_type: "application/octet-stream".into(), data: data.clone() })
+            .build();
+        let text = String::from_utf8(raw.as_bytes().to_vec()).unwrap();
+        let boundary = text.split("boundary=\"").nth(1).and_then(|r| r.split('"').next()).unwrap();
+        let parts: Vec<&str> = text.split(&format!("--{}", boundary)).collect();
+        // preamble, text part, attachment, closing "--"
+        assert_eq!(parts.len(), 4);
+        assert!(parts[1].contains("Content-Type: text/plain; charset=utf-8\r\n"));
+        assert_eq!(parts[3], "--\r\n");
+
+        let attachment = parts[2];
+        assert!(attachment.contains("Content-Disposition: attachment; filename=\"data.bin\"\r\n"));
+        assert!(attachment.contains("Content-Transfer-Encoding: base64\r\n"));
+        let (_, body) = attachment.split_once("\r\n\r\n").unwrap();
+        let lines: Vec<&str> = body.trim_end_matches("\r\n").split("\r\n").collect();
+        assert!(lines.iter().all(|l| l.len() <= 76));
+        assert_eq!(base64::engine::general_purpose::STANDARD.decode(lines.concat()).unwrap(), data);
+    }
+}
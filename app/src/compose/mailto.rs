@@ -0,0 +1,116 @@
+//! `mailto:` URIs (RFC 6068), e.g. from a browser's mail link handler.
+
+use super::Draft;
+
+// %XX -> byte; everything else (including '+', which is not a space here) is literal
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        // from_str_radix alone would take "%+1" as 1
+        if bytes[i] == b'%'
+            && let Some(hex) = s.get(i + 1..i + 3).filter(|h| h.bytes().all(|b| b.is_ascii_hexdigit()))
+            && let Ok(b) = u8::from_str_radix(hex, 16)
+        {
+            out.push(b);
+            i += 3;
+            continue;
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    // RFC 6068 §2: percent-encoded octets are UTF-8
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+// header values can't carry line breaks; an encoded CRLF in a subject becomes a space
+fn one_line(s: &str) -> String {
+    s.split(['\r', '\n']).filter(|p| !p.is_empty()).collect::<Vec<_>>().join(" ")
+}
+
+fn append(list: &mut String, addrs: &str) {
+    let addrs = one_line(addrs);
+    if addrs.trim().is_empty() {
+        return;
+    }
+    if !list.is_empty() {
+        list.push_str(", ");
+    }
+    list.push_str(addrs.trim());
+}
+
+/// A draft prefilled from a `mailto:` URI: the recipients in the path plus the to, cc, bcc,
+/// subject, body and in-reply-to fields. Other header fields are ignored, as RFC 6068 advises.
+pub fn parse(uri: &str) -> Result<Draft, String> {
+    let rest = uri
+        .get(..7)
+        .filter(|scheme| scheme.eq_ignore_ascii_case("mailto:"))
+        .map(|_| &uri[7..])
+        .ok_or_else(|| format!("not a mailto: URI: {}", uri))?;
+    let (path, query) = rest.split_once('?').unwrap_or((rest, ""));
+
+    let (mut to, mut cc, mut bcc, mut subject, mut body) = (String::new(), String::new(), String::new(), String::new(), String::new());
+    let mut in_reply_to = None;
+    append(&mut to, &percent_decode(path));
+    for field in query.split('&').filter(|f| !f.is_empty()) {
+        let (name, value) = field.split_once('=').unwrap_or((field, ""));
+        let value = percent_decode(value);
+        match percent_decode(name).to_lowercase().as_str() {
+            "to" => append(&mut to, &value),
+            "cc" => append(&mut cc, &value),
+            "bcc" => append(&mut bcc, &value),
+            "subject" => subject = one_line(&value),
+            "body" => body = value.replace("\r\n", "\n"),
+            "in-reply-to" => in_reply_to = Some(one_line(&value)).filter(|v| !v.is_empty()),
+            _ => {}
+        }
+    }
+
+    // go through the compose file format so the fields land exactly where the editor would put them
+    let mut draft = Draft::default();
+    draft.apply_compose_file(&format!("To: {}\nCc: {}\nBcc: {}\nSubject: {}\n\n{}", to, cc, bcc, subject, body));
+    draft.references = in_reply_to.clone();
+    draft.in_reply_to = in_reply_to;
+    Ok(draft)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_two_hex_digits_make_an_escape() {
+        assert_eq!(percent_decode("a%20b"), "a b");
+        assert_eq!(percent_decode("%+1%-f%2"), "%+1%-f%2");
+        assert_eq!(percent_decode("100%"), "100%");
+    }
+
+    #[test]
+    fn utf8_in_subject_and_body() {
+        let d = parse("mailto:a@example.com?subject=Caf%C3%A9%20%F0%9F%8E%89&body=gr%C3%BC%C3%9Fe").unwrap();
+        assert_eq!(d.subject, "Café 🎉");
+        assert_eq!(d.body, "grüße");
+    }
+
+    #[test]
+    fn line_breaks_stay_out_of_the_subject() {
+        let d = parse("mailto:a@example.com?subject=one%0D%0ABcc:%20evil@example.com&body=line%0D%0Anext").unwrap();
+        assert_eq!(d.subject, "one Bcc: evil@example.com");
+        assert!(d.bcc.is_empty());
+        assert_eq!(d.body, "line\nnext");
+    }
+
+    #[test]
+    fn repeated_to_fields_add_up() {
+        let d = parse("mailto:a@example.com?to=b@example.com&to=c@example.com&cc=d@example.com").unwrap();
+        assert_eq!(d.to, "a@example.com, b@example.com, c@example.com");
+        assert_eq!(d.cc, "d@example.com");
+    }
+
+    #[test]
+    fn scheme_is_case_insensitive() {
+        assert_eq!(parse("MAILTO:a@example.com").unwrap().to, "a@example.com");
+        assert!(parse("https://example.com").is_err());
+    }
+}
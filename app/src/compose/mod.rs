@@ -0,0 +1,376 @@
+pub mod builder;
+pub mod mailto;
+
+use crate::gmail::{GmailDraft, SendAs, SimpleMail};
+use builder::{Attachment, MessageBuilder, RawMessage};
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::PathBuf;
+
+/// A message being composed. The editable fields are written to the compose file;
+/// the threading fields travel alongside it untouched.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Draft {
+    pub from: String,
+    pub to: String,
+    pub cc: String,
+    pub bcc: String,
+    pub reply_to: String,
+    pub subject: String,
+    pub body: String,
+    /// file paths, one `Attach:` pseudo-header each in the compose file
+    pub attachments: Vec<String>,
+    /// signature of the chosen `From` identity, appended below the body when the message is built
+    pub signature: String,
+    pub in_reply_to: Option<String>,
+    pub references: Option<String>,
+    pub thread_id: Option<String>,
+    /// set once the message has been saved as a Gmail draft
+    pub draft_id: Option<String>,
+}
+
+impl Draft {
+    /// Resume editing a saved Gmail draft.
+    pub fn from_gmail_draft(d: &GmailDraft) -> Self {
+        let m = &d.message;
+        Draft {
+            from: m.from.clone().unwrap_or_default(),
+            to: m.to.clone().unwrap_or_default(),
+            cc: m.cc.clone().unwrap_or_default(),
+            bcc: d.bcc.clone().unwrap_or_default(),
+            reply_to: m.reply_to.clone().unwrap_or_default(),
+            subject: m.subject.clone().unwrap_or_default(),
+            body: m.body.clone().unwrap_or_default(),
+            attachments: Vec::new(),
+            // a resumed draft already carries its signature in the body
+            signature: String::new(),
+            in_reply_to: m.in_reply_to.clone(),
+            references: m.references.clone(),
+            thread_id: m.thread_id.clone(),
+            draft_id: Some(d.id.clone()),
+        }
+    }
+
+    /// Send from `identity`: sets `From`, its Reply-To (unless one is already set) and signature.
+    pub fn use_identity(&mut self, identity: &SendAs) {
+        self.from = identity.address();
+        if self.reply_to.is_empty() {
+            self.reply_to = identity.reply_to.clone().unwrap_or_default();
+        }
+        self.signature = identity.signature.clone().unwrap_or_default();
+    }
+
+    /// Body as it will be sent, signature included.
+    pub fn full_body(&self) -> String {
+        if self.signature.is_empty() {
+            self.body.clone()
+        } else {
+            format!("{}\n-- \n{}", self.body.trim_end_matches('\n'), self.signature)
+        }
+    }
+
+    /// Header lines and body as written to the compose file.
+    pub fn to_compose_file(&self) -> String {
+        let mut out = String::new();
+        if !self.from.is_empty() {
+            out.push_str(&format!("From: {}\n", self.from));
+        }
+        out.push_str(&format!("To: {}\nCc: {}\nBcc: {}\n", self.to, self.cc, self.bcc));
+        if !self.reply_to.is_empty() {
+            out.push_str(&format!("Reply-To: {}\n", self.reply_to));
+        }
+        out.push_str(&format!("Subject: {}\n", self.subject));
+        for a in &self.attachments {
+            out.push_str(&format!("Attach: {}\n", a));
+        }
+        out.push_str(&format!("\n{}\n", self.body));
+        out
+    }
+
+    /// Read the user's edits back from the compose file; headers end at the first blank line.
+    pub fn apply_compose_file(&mut self, content: &str) {
+        let mut lines = content.lines();
+        self.from.clear();
+        self.reply_to.clear();
+        self.to.clear();
+        self.cc.clear();
+        self.bcc.clear();
+        self.subject.clear();
+        self.attachments.clear();
+        for l in lines.by_ref() {
+            let l = l.trim_end();
+            if l.is_empty() {
+                break;
+            }
+            if l.to_lowercase().starts_with("from:") {
+                self.from = l[5..].trim().to_string();
+            } else if l.to_lowercase().starts_with("reply-to:") {
+                self.reply_to = l[9..].trim().to_string();
+            } else if l.to_lowercase().starts_with("to:") {
+                self.to = l[3..].trim().to_string();
+            } else if l.to_lowercase().starts_with("cc:") {
+                self.cc = l[3..].trim().to_string();
+            } else if l.to_lowercase().starts_with("bcc:") {
+                self.bcc = l[4..].trim().to_string();
+            } else if l.to_lowercase().starts_with("subject:") {
+                self.subject = l[8..].trim().to_string();
+            } else if l.to_lowercase().starts_with("attach:") {
+                let path = l[7..].trim();
+                if !path.is_empty() {
+                    self.attachments.push(path.to_string());
+                }
+            }
+        }
+        self.body = lines.collect::<Vec<&str>>().join("\n");
+    }
+
+    /// Build the outgoing message; fails if an attachment can't be read.
+    pub fn to_message(&self) -> io::Result<RawMessage> {
+        let mut b = MessageBuilder::new()
+            .from(&self.from)
+            .reply_to(&self.reply_to)
+            .to(&self.to)
+            .cc(&self.cc)
+            .bcc(&self.bcc)
+            .subject(&self.subject)
+            .body(&self.full_body());
+        if let Some(id) = &self.in_reply_to {
+            b = b.in_reply_to(id);
+        }
+        if let Some(refs) = &self.references {
+            b = b.references(refs);
+        }
+        for path in &self.attachments {
+            let attachment = Attachment::from_path(&expand_home(path))
+                .map_err(|e| io::Error::new(e.kind(), format!("attachment {}: {}", path, e)))?;
+            b = b.attach(attachment);
+        }
+        Ok(b.build())
+    }
+}
+
+// "~/foo" -> "$HOME/foo"
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Identity to send from: for replies the one the original was addressed to,
+/// otherwise the account default.
+pub fn pick_identity<'a>(identities: &'a [SendAs], orig: Option<&SimpleMail>) -> Option<&'a SendAs> {
+    let recipients: Vec<String> = orig
+        .map(|m| {
+            let mut r = split_addresses(m.to.as_deref().unwrap_or(""));
+            r.extend(split_addresses(m.cc.as_deref().unwrap_or("")));
+            r.iter().map(|a| addr_spec(a).to_lowercase()).collect()
+        })
+        .unwrap_or_default();
+    identities
+        .iter()
+        .find(|i| recipients.contains(&i.email.to_lowercase()))
+        .or_else(|| identities.iter().find(|i| i.is_default))
+        .or_else(|| identities.first())
+}
+
+/// The identity whose address is in `from`, if any.
+pub fn identity_for<'a>(identities: &'a [SendAs], from: &str) -> Option<&'a SendAs> {
+    let spec = addr_spec(from);
+    identities.iter().find(|i| i.email.eq_ignore_ascii_case(&spec))
+}
+
+// split an address list on commas that are not inside quotes or angle brackets
+pub fn split_addresses(list: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut cur = String::new();
+    let mut in_quotes = false;
+    let mut in_angle = false;
+    for c in list.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            '<' if !in_quotes => in_angle = true,
+            '>' if !in_quotes => in_angle = false,
+            ',' if !in_quotes && !in_angle => {
+                if !cur.trim().is_empty() {
+                    out.push(cur.trim().to_string());
+                }
+                cur.clear();
+                continue;
+            }
+            _ => {}
+        }
+        cur.push(c);
+    }
+    if !cur.trim().is_empty() {
+        out.push(cur.trim().to_string());
+    }
+    out
+}
+
+// "Alice <alice@example.com>" -> "alice@example.com"
+pub fn addr_spec(addr: &str) -> String {
+    match (addr.rfind('<'), addr.rfind('>')) {
+        (Some(a), Some(b)) if a < b => addr[a + 1..b].trim().to_string(),
+        _ => addr.trim().to_string(),
+    }
+}
+
+fn prefixed_subject(prefix: &str, subject: Option<&str>) -> String {
+    let subject = subject.unwrap_or("").trim();
+    if subject.to_lowercase().starts_with(&format!("{}:", prefix.to_lowercase())) {
+        subject.to_string()
+    } else {
+        format!("{}: {}", prefix, subject)
+    }
+}
+
+fn original_text(m: &SimpleMail) -> String {
+    m.body.clone().or_else(|| m.snippet.clone()).unwrap_or_default()
+}
+
+/// Reply to `orig`. With `all`, everyone on To/Cc is kept except the user's own addresses.
+/// `From` is left empty; the caller fills in the sending identity.
+pub fn reply(orig: &SimpleMail, all: bool, own_addresses: &[String]) -> Draft {
+    let is_own = |a: &str| own_addresses.iter().any(|o| o.eq_ignore_ascii_case(&addr_spec(a)));
+
+    // Reply-To wins over From, as mail clients are expected to honour it
+    let sender = orig.reply_to.clone().or_else(|| orig.from.clone()).unwrap_or_default();
+    let mut to: Vec<String> = split_addresses(&sender);
+    let mut cc: Vec<String> = Vec::new();
+    if all {
+        to.extend(split_addresses(orig.to.as_deref().unwrap_or("")));
+        cc.extend(split_addresses(orig.cc.as_deref().unwrap_or("")));
+    }
+
+    let mut seen: Vec<String> = Vec::new();
+    let mut keep = |a: &String| {
+        let spec = addr_spec(a).to_lowercase();
+        if is_own(a) || seen.contains(&spec) {
+            return false;
+        }
+        seen.push(spec);
+        true
+    };
+    to.retain(&mut keep);
+    cc.retain(&mut keep);
+    // replying to your own sent mail goes back to its recipients
+    if to.is_empty() {
+        to = split_addresses(orig.to.as_deref().unwrap_or(""));
+    }
+
+    let attribution = format!(
+        "On {}, {} wrote:",
+        orig.date.as_deref().unwrap_or("an unknown date"),
+        orig.from.as_deref().unwrap_or("unknown")
+    );
+    let quoted: Vec<String> = original_text(orig)
+        .lines()
+        .map(|l| if l.is_empty() { ">".to_string() } else { format!("> {}", l) })
+        .collect();
+
+    let references = match (&orig.references, &orig.message_id) {
+        (Some(r), Some(id)) => Some(format!("{} {}", r, id)),
+        (None, Some(id)) => Some(id.clone()),
+        (r, None) => r.clone(),
+    };
+
+    Draft {
+        from: String::new(),
+        to: to.join(", "),
+        cc: cc.join(", "),
+        bcc: String::new(),
+        reply_to: String::new(),
+        subject: prefixed_subject("Re", orig.subject.as_deref()),
+        body: format!("\n\n{}\n{}\n", attribution, quoted.join("\n")),
+        attachments: Vec::new(),
+        signature: String::new(),
+        in_reply_to: orig.message_id.clone(),
+        references,
+        thread_id: orig.thread_id.clone(),
+        draft_id: None,
+    }
+}
+
+/// Forward `orig` inline with the usual forwarded-message header block.
+pub fn forward(orig: &SimpleMail) -> Draft {
+    let mut block = String::from("---------- Forwarded message ---------\n");
+    block.push_str(&format!("From: {}\n", orig.from.as_deref().unwrap_or("")));
+    block.push_str(&format!("Date: {}\n", orig.date.as_deref().unwrap_or("")));
+    block.push_str(&format!("Subject: {}\n", orig.subject.as_deref().unwrap_or("")));
+    block.push_str(&format!("To: {}\n", orig.to.as_deref().unwrap_or("")));
+    if let Some(cc) = &orig.cc {
+        block.push_str(&format!("Cc: {}\n", cc));
+    }
+
+    Draft {
+        subject: prefixed_subject("Fwd", orig.subject.as_deref()),
+        body: format!("\n\n{}\n{}\n", block, original_text(orig)),
+        ..Draft::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mail(from: &str, reply_to: Option<&str>, to: &str, cc: &str) -> SimpleMail {
+        SimpleMail {
+            id: "m1".into(),
+            thread_id: Some("t1".into()),
+            subject: Some("Plans".into()),
+            from: Some(from.into()),
+            reply_to: reply_to.map(str::to_string),
+            to: Some(to.into()),
+            cc: Some(cc.into()),
+            message_id: Some("<m1@example.com>".into()),
+            references: Some("<m0@example.com>".into()),
+            ..SimpleMail::default()
+        }
+    }
+
+    fn own() -> Vec<String> {
+        vec!["me@example.com".to_string(), "alias@example.com".to_string()]
+    }
+
+    #[test]
+    fn reply_to_wins_over_from() {
+        let orig = mail("Ann <ann@example.com>", Some("List <list@example.com>"), "me@example.com", "");
+        assert_eq!(reply(&orig, false, &own()).to, "List <list@example.com>");
+        assert_eq!(reply(&orig, true, &own()).to, "List <list@example.com>");
+        let orig = mail("Ann <ann@example.com>", None, "me@example.com", "");
+        assert_eq!(reply(&orig, false, &own()).to, "Ann <ann@example.com>");
+    }
+
+    #[test]
+    fn reply_all_drops_own_addresses_and_duplicates() {
+        let orig = mail(
+            "Ann <ann@example.com>",
+            None,
+            "Me <ME@Example.com>, Bob <bob@example.com>, ANN@example.com",
+            "Alias@Example.COM, \"Carol, C\" <carol@example.com>, BOB@example.com",
+        );
+        let draft = reply(&orig, true, &own());
+        assert_eq!(draft.to, "Ann <ann@example.com>, Bob <bob@example.com>");
+        assert_eq!(draft.cc, "\"Carol, C\" <carol@example.com>");
+        // a plain reply leaves everyone else out
+        let draft = reply(&orig, false, &own());
+        assert_eq!((draft.to.as_str(), draft.cc.as_str()), ("Ann <ann@example.com>", ""));
+    }
+
+    #[test]
+    fn replying_to_own_mail_goes_to_its_recipients() {
+        let orig = mail("Me <me@example.com>", None, "Bob <bob@example.com>", "");
+        assert_eq!(reply(&orig, true, &own()).to, "Bob <bob@example.com>");
+        assert_eq!(reply(&orig, false, &own()).to, "Bob <bob@example.com>");
+    }
+
+    #[test]
+    fn replies_thread_onto_the_original() {
+        let draft = reply(&mail("ann@example.com", None, "me@example.com", ""), false, &own());
+        assert_eq!(draft.subject, "Re: Plans");
+        assert_eq!(draft.in_reply_to.as_deref(), Some("<m1@example.com>"));
+        assert_eq!(draft.references.as_deref(), Some("<m0@example.com> <m1@example.com>"));
+        assert_eq!(draft.thread_id.as_deref(), Some("t1"));
+    }
+}
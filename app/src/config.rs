@@ -0,0 +1,330 @@
+//! `config.toml` in the config dir. Every section is optional:
+//!
+//! ```toml
+//! [[accounts]]
+//! name = "personal"
+//! provider = "gmail"            # or "outlook"
+//! email = "me@example.com"
+//! client_id = "...apps.googleusercontent.com"
+//! client_secret = "..."
+//! default = true
+//!
+//! [sync]
+//! interval_seconds = 60
+//! page_size = 25
+//!
+//! [compose]
+//! editor = "vim"                # used when $EDITOR is unset
+//! composer = "external"         # or "builtin"
+//!
+//! [layout]
+//! list_mode = "threads"         # or "messages"
+//! preview = "below"             # "right" (default), "below" or "off"
+//! preview_size = 40             # percent of the screen for the preview pane
+//! columns = ["flags", "sender:30", "subject", "labels", "size", "date"]   # see ui/columns.rs
+//!
+//! [theme]                       # see theme.rs for styles and defining themes
+//! base = "light"                # dark (default), light, high-contrast or 16-color
+//! unread = { fg = "blue", bold = true }
+//!
+//! [colors]                      # shorthand for the theme's selection, error and dim colors
+//! highlight = "yellow"          # names, "#rrggbb" or a 0-255 index
+//!
+//! [keybindings]                 # see keymap.rs for the key syntax and action names
+//! preset = "vim"                # or "emacs"; "default" otherwise
+//! compose = "m"
+//! down = ["j", "C-n"]
+//!
+//! [log]
+//! level = "debug"               # off, error, warn, info (default), debug or trace
+//! file = "/tmp/mailtui.log"     # defaults to mailtui.log in the state dir
+//! ```
+//!
+//! `MAIL_OAUTH_CLIENT_ID`, `MAIL_OAUTH_CLIENT_SECRET`, `MAIL_FETCH_INTERVAL_SECONDS` and
+//! `MAIL_COMPOSER` override the file.
+
+use log::LevelFilter;
+use ratatui::style::Color;
+use serde::{Deserialize, Deserializer};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::keymap::Keymap;
+use crate::storage::config_dir;
+use crate::theme::{Theme, ThemeDef};
+use crate::ui::columns::Column;
+use crate::ui::{ListMode, Preview};
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub accounts: Vec<Account>,
+    pub sync: SyncSettings,
+    pub compose: Compose,
+    pub layout: Layout,
+    pub theme: ThemeDef,
+    /// themes `theme.base` can name, besides the built-in ones
+    pub themes: BTreeMap<String, ThemeDef>,
+    pub colors: Colors,
+    pub keybindings: Keymap,
+    pub log: LogSettings,
+    /// account picked with `--account`
+    #[serde(skip)]
+    pub selected: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Provider {
+    #[default]
+    Gmail,
+    Outlook,
+}
+
+impl Provider {
+    pub fn name(self) -> &'static str {
+        match self {
+            Provider::Gmail => "gmail",
+            Provider::Outlook => "outlook",
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Account {
+    pub name: String,
+    #[serde(default)]
+    pub provider: Provider,
+    pub email: Option<String>,
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+    #[serde(default)]
+    pub default: bool,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct SyncSettings {
+    /// seconds between inbox fetches; at least 1
+    #[serde(deserialize_with = "positive")]
+    pub interval_seconds: u64,
+    /// how many inbox messages each fetch pulls in
+    pub page_size: usize,
+}
+
+impl Default for SyncSettings {
+    fn default() -> Self {
+        SyncSettings { interval_seconds: 5, page_size: 10 }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ComposerKind {
+    Builtin,
+    External,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Compose {
+    pub editor: Option<String>,
+    /// unset: $EDITOR when there is one, the built-in form otherwise
+    pub composer: Option<ComposerKind>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Layout {
+    #[serde(deserialize_with = "list_mode")]
+    pub list_mode: ListMode,
+    #[serde(deserialize_with = "preview")]
+    pub preview: Preview,
+    /// percent of the list area the preview takes
+    pub preview_size: u16,
+    /// fields of the message list, left to right
+    #[serde(deserialize_with = "columns")]
+    pub columns: Vec<Column>,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Layout { list_mode: ListMode::default(), preview: Preview::Right, preview_size: 50, columns: Column::defaults() }
+    }
+}
+
+/// Single-color overrides of the theme's selection, error and dim styles, for when a whole
+/// `[theme]` table is more than needed.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Colors {
+    /// selection and notices
+    #[serde(deserialize_with = "color")]
+    pub highlight: Option<Color>,
+    #[serde(deserialize_with = "color")]
+    pub error: Option<Color>,
+    #[serde(deserialize_with = "color")]
+    pub dim: Option<Color>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogSettings {
+    #[serde(deserialize_with = "level")]
+    pub level: LevelFilter,
+    pub file: Option<PathBuf>,
+}
+
+impl Default for LogSettings {
+    fn default() -> Self {
+        LogSettings { level: LevelFilter::Info, file: None }
+    }
+}
+
+fn list_mode<'de, D: Deserializer<'de>>(d: D) -> Result<ListMode, D::Error> {
+    match String::deserialize(d)?.as_str() {
+        "messages" => Ok(ListMode::Messages),
+        "threads" => Ok(ListMode::Threads),
+        other => Err(serde::de::Error::custom(format!("unknown list mode {:?}, expected \"messages\" or \"threads\"", other))),
+    }
+}
+
+fn preview<'de, D: Deserializer<'de>>(d: D) -> Result<Preview, D::Error> {
+    match String::deserialize(d)?.as_str() {
+        "right" => Ok(Preview::Right),
+        "below" => Ok(Preview::Below),
+        "off" => Ok(Preview::Off),
+        other => Err(serde::de::Error::custom(format!("unknown preview position {:?}, expected \"right\", \"below\" or \"off\"", other))),
+    }
+}
+
+fn columns<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<Column>, D::Error> {
+    Vec::<String>::deserialize(d)?.iter().map(|c| c.parse().map_err(serde::de::Error::custom)).collect()
+}
+
+fn color<'de, D: Deserializer<'de>>(d: D) -> Result<Option<Color>, D::Error> {
+    let s = String::deserialize(d)?;
+    s.parse().map(Some).map_err(|_| serde::de::Error::custom(format!("unknown color {:?}", s)))
+}
+
+fn level<'de, D: Deserializer<'de>>(d: D) -> Result<LevelFilter, D::Error> {
+    let s = String::deserialize(d)?;
+    s.parse().map_err(|_| serde::de::Error::custom(format!("unknown log level {:?}, expected off, error, warn, info, debug or trace", s)))
+}
+
+pub fn config_file() -> PathBuf {
+    config_dir().join("config.toml")
+}
+
+fn positive<'de, D: Deserializer<'de>>(d: D) -> Result<u64, D::Error> {
+    match u64::deserialize(d)? {
+        0 => Err(serde::de::Error::custom("must be at least 1")),
+        n => Ok(n),
+    }
+}
+
+fn env(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|v| !v.is_empty())
+}
+
+impl Config {
+    /// Read `config.toml`, or `path` instead (defaults when it doesn't exist), and apply
+    /// environment overrides. Errors name the file and, for syntax or value problems, the line and column.
+    pub fn load(path: Option<&Path>) -> Result<Config, String> {
+        let path = path.map(Path::to_path_buf).unwrap_or_else(config_file);
+        let mut cfg = match std::fs::read_to_string(&path) {
+            Ok(text) => toml::from_str::<Config>(&text).map_err(|e| format!("{}: {}", path.display(), e))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Config::default(),
+            Err(e) => return Err(format!("{}: {}", path.display(), e)),
+        };
+        if let Some(secs) = env("MAIL_FETCH_INTERVAL_SECONDS") {
+            cfg.sync.interval_seconds = secs
+                .parse()
+                .ok()
+                .filter(|n| *n > 0)
+                .ok_or_else(|| format!("MAIL_FETCH_INTERVAL_SECONDS: not a positive number: {:?}", secs))?;
+        }
+        match env("MAIL_COMPOSER").as_deref() {
+            Some("builtin") => cfg.compose.composer = Some(ComposerKind::Builtin),
+            Some("external") => cfg.compose.composer = Some(ComposerKind::External),
+            _ => {}
+        }
+        cfg.theme().map_err(|e| format!("{}: theme: {}", path.display(), e))?;
+        Ok(cfg)
+    }
+
+    /// Use the account called `name` instead of the default one.
+    pub fn select_account(&mut self, name: &str) -> Result<(), String> {
+        if !self.accounts.iter().any(|a| a.name == name) {
+            let known: Vec<&str> = self.accounts.iter().map(|a| a.name.as_str()).collect();
+            return Err(format!("no account named {:?} (configured: {})", name, known.join(", ")));
+        }
+        self.selected = Some(name.to_string());
+        Ok(())
+    }
+
+    /// The `--account` one, else the default one.
+    pub fn account(&self) -> Option<&Account> {
+        match &self.selected {
+            Some(name) => self.accounts.iter().find(|a| &a.name == name),
+            None => self.default_account(),
+        }
+    }
+
+    /// The account marked `default`, else the first one.
+    fn default_account(&self) -> Option<&Account> {
+        self.accounts.iter().find(|a| a.default).or(self.accounts.first())
+    }
+
+    /// True when the default account is in use; it inherits state saved before accounts existed.
+    pub fn is_default_account(&self) -> bool {
+        match (self.account(), self.default_account()) {
+            (Some(a), Some(d)) => a.name == d.name,
+            _ => false,
+        }
+    }
+
+    /// OAuth client credentials: the env vars win over the account's.
+    pub fn credentials(&self) -> Option<(String, String)> {
+        let account = self.account();
+        let id = env("MAIL_OAUTH_CLIENT_ID").or_else(|| account.and_then(|a| a.client_id.clone()))?;
+        let secret = env("MAIL_OAUTH_CLIENT_SECRET")
+            .or_else(|| account.and_then(|a| a.client_secret.clone()))
+            .unwrap_or_default();
+        Some((id, secret))
+    }
+
+    /// The `[theme]` with `[colors]` on top.
+    pub fn theme(&self) -> Result<Theme, String> {
+        let mut theme = self.theme.resolve(&self.themes)?;
+        if let Some(c) = self.colors.highlight {
+            theme.selection = theme.selection.fg(c);
+            theme.accent = theme.accent.fg(c);
+        }
+        if let Some(c) = self.colors.error {
+            theme.error = theme.error.fg(c);
+        }
+        if let Some(c) = self.colors.dim {
+            theme.dim = theme.dim.fg(c);
+        }
+        Ok(theme)
+    }
+
+    /// Editor command: $EDITOR, then `compose.editor`.
+    pub fn editor(&self) -> Option<String> {
+        env("EDITOR").or_else(|| self.compose.editor.clone())
+    }
+}
+
+/// Install the loaded config; call once at startup.
+pub fn init(cfg: Config) {
+    let _ = CONFIG.set(cfg);
+}
+
+pub fn get() -> &'static Config {
+    CONFIG.get_or_init(Config::default)
+}
@@ -0,0 +1,31 @@
+//! Updates from background threads to the UI loop, which owns the app state.
+
+use std::sync::OnceLock;
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use crate::gmail::{LabelNames, SimpleMail};
+
+static SENDER: OnceLock<Sender<AppEvent>> = OnceLock::new();
+
+pub enum AppEvent {
+    /// the inbox after a sync, with the names of the labels it uses
+    Inbox { messages: Vec<SimpleMail>, labels: LabelNames },
+    /// outcome of a `/` search
+    SearchResults { query: String, result: Result<Vec<SimpleMail>, String> },
+    /// something outside the app state changed, e.g. the status line
+    Redraw,
+}
+
+/// Start delivering events; call once, before spawning workers.
+pub fn subscribe() -> Receiver<AppEvent> {
+    let (tx, rx) = mpsc::channel();
+    let _ = SENDER.set(tx);
+    rx
+}
+
+/// Hand an event to the UI loop. A no-op when nothing is listening, e.g. in the CLI.
+pub fn send(event: AppEvent) {
+    if let Some(tx) = SENDER.get() {
+        let _ = tx.send(event);
+    }
+}
@@ -1,15 +1,64 @@
+use std::collections::HashMap;
 use std::error::Error;
 use base64::Engine;
 use reqwest::blocking::Client;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+use crate::compose::builder::RawMessage;
+
+/// A non-success HTTP response from the Gmail API.
+#[derive(Debug)]
+pub struct ApiError {
+    pub status: u16,
+    pub message: String,
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl Error for ApiError {}
+
+// status plus the response body, e.g. "gmail send API error: 403 Forbidden - {...}"
+fn api_error(context: &str, res: reqwest::blocking::Response) -> Box<dyn Error + Send + Sync> {
+    let status = res.status();
+    let b = res.text().unwrap_or_else(|_| "<failed to read body>".into());
+    Box::new(ApiError { status: status.as_u16(), message: format!("{}: {} - {}", context, status, b) })
+}
+
+// messages above this go through the resumable upload endpoint instead of a JSON `raw` body
+const SIMPLE_SEND_LIMIT: usize = 5 * 1024 * 1024;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
 pub struct SimpleMail {
     pub id: String,
+    pub thread_id: Option<String>,
     pub subject: Option<String>,
     pub from: Option<String>,
+    pub to: Option<String>,
+    pub cc: Option<String>,
+    pub reply_to: Option<String>,
     pub date: Option<String>,
+    pub message_id: Option<String>,
+    pub in_reply_to: Option<String>,
+    pub references: Option<String>,
     pub snippet: Option<String>,
+    /// decoded text/plain body, when the message has one
+    pub body: Option<String>,
+    /// Gmail's receive time in milliseconds since the epoch; used for chronological ordering
+    pub internal_date: Option<i64>,
+    /// true if any MIME part carries a filename
+    pub has_attachment: bool,
+    /// from the UNREAD and STARRED labels
+    pub unread: bool,
+    pub starred: bool,
+    /// Gmail's size estimate in bytes
+    pub size: Option<u32>,
+    /// ids of the message's labels, without those shown otherwise (inbox, unread, starred);
+    /// `label_name` turns them into names
+    pub labels: Vec<String>,
 }
 
 #[derive(Deserialize)]
@@ -22,15 +71,60 @@ struct MessageId {
 }
 
 #[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct MessageFull {
     id: String,
+    thread_id: Option<String>,
     snippet: Option<String>,
+    // int64 is sent as a JSON string
+    internal_date: Option<String>,
+    label_ids: Option<Vec<String>>,
+    size_estimate: Option<u32>,
     payload: Option<Payload>,
 }
 #[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct Payload {
+    mime_type: Option<String>,
+    filename: Option<String>,
     headers: Option<Vec<Header>>,
+    body: Option<PartBody>,
+    parts: Option<Vec<Payload>>,
+}
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PartBody {
+    data: Option<String>,
+    attachment_id: Option<String>,
+}
+#[derive(Deserialize)]
+struct DraftsListResp {
+    drafts: Option<Vec<DraftRef>>,
+}
+#[derive(Deserialize)]
+struct DraftRef {
+    id: String,
+}
+#[derive(Deserialize)]
+struct DraftFull {
+    id: String,
+    message: MessageFull,
+}
+
+#[derive(Deserialize)]
+struct ThreadFull {
+    messages: Option<Vec<MessageFull>>,
 }
+#[derive(Deserialize)]
+struct LabelsResp {
+    labels: Option<Vec<Label>>,
+}
+#[derive(Deserialize)]
+struct Label {
+    id: String,
+    name: String,
+}
+
 #[derive(Deserialize)]
 struct Header {
     name: String,
@@ -43,15 +137,132 @@ fn header_value(headers: Option<&Vec<Header>>, name: &str) -> Option<String> {
         .map(|h| h.value.clone())
 }
 
+// depth-first search for the first text/plain part
+fn plain_text_body(p: &Payload) -> Option<String> {
+    if p.mime_type.as_deref().is_some_and(|m| m.eq_ignore_ascii_case("text/plain"))
+        && let Some(data) = p.body.as_ref().and_then(|b| b.data.as_ref())
+    {
+        // Gmail uses base64url; padding is sometimes present, sometimes not
+        let bytes = base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .decode(data.trim_end_matches('='))
+            .ok()?;
+        return Some(String::from_utf8_lossy(&bytes).replace("\r\n", "\n"));
+    }
+    p.parts.as_ref()?.iter().find_map(plain_text_body)
+}
+
+// every part with a filename, depth first
+fn attachment_parts(p: &Payload, out: &mut Vec<DraftAttachment>) {
+    if let Some(filename) = p.filename.as_deref().filter(|f| !f.is_empty()) {
+        out.push(DraftAttachment {
+            filename: filename.to_string(),
+            attachment_id: p.body.as_ref().and_then(|b| b.attachment_id.clone()),
+            data: p.body.as_ref().and_then(|b| b.data.clone()),
+        });
+    }
+    for part in p.parts.iter().flatten() {
+        attachment_parts(part, out);
+    }
+}
+
+fn has_attachment(p: &Payload) -> bool {
+    p.filename.as_deref().is_some_and(|f| !f.is_empty()) || p.parts.iter().flatten().any(has_attachment)
+}
+
+/// User label ids ("Label_12") to their names.
+pub type LabelNames = HashMap<String, String>;
+
+/// The names of the account's labels.
+pub fn fetch_label_names(access_token: &str) -> Result<LabelNames, Box<dyn Error + Send + Sync>> {
+    let res = Client::new()
+        .get("https://gmail.googleapis.com/gmail/v1/users/me/labels")
+        .bearer_auth(access_token)
+        .send()?;
+    if !res.status().is_success() {
+        return Err(api_error("gmail labels API error", res));
+    }
+    let labels: LabelsResp = res.json()?;
+    Ok(labels.labels.unwrap_or_default().into_iter().map(|l| (l.id, l.name)).collect())
+}
+
+// labels the list shows in other ways (or not at all)
+fn shown_label(id: &str) -> bool {
+    !matches!(id, "INBOX" | "UNREAD" | "STARRED" | "CATEGORY_PERSONAL")
+}
+
+/// A label as the list shows it: "CATEGORY_UPDATES" -> "updates", "Label_12" -> its name
+/// (the id while the name is unknown).
+pub fn label_name(id: &str, names: &LabelNames) -> String {
+    if id.starts_with("Label_") {
+        return names.get(id).cloned().unwrap_or_else(|| id.to_string());
+    }
+    id.strip_prefix("CATEGORY_").unwrap_or(id).to_lowercase()
+}
+
+/// True when `msgs` carry a user label missing from `names`, i.e. they need refetching.
+pub fn has_unknown_labels(msgs: &[SimpleMail], names: &LabelNames) -> bool {
+    msgs.iter().flat_map(|m| &m.labels).any(|l| l.starts_with("Label_") && !names.contains_key(l))
+}
+
+fn to_simple(mf: MessageFull) -> SimpleMail {
+    let headers = mf.payload.as_ref().and_then(|p| p.headers.as_ref());
+    let subject = header_value(headers, "Subject");
+    let from = header_value(headers, "From");
+    let date = header_value(headers, "Date");
+    let to = header_value(headers, "To");
+    let cc = header_value(headers, "Cc");
+    let reply_to = header_value(headers, "Reply-To");
+    let message_id = header_value(headers, "Message-ID");
+    let in_reply_to = header_value(headers, "In-Reply-To");
+    let references = header_value(headers, "References");
+    let body = mf.payload.as_ref().and_then(plain_text_body);
+    let has_attachment = mf.payload.as_ref().is_some_and(has_attachment);
+    let has_label = |l: &str| mf.label_ids.iter().flatten().any(|id| id == l);
+    let (unread, starred) = (has_label("UNREAD"), has_label("STARRED"));
+    let labels = mf.label_ids.iter().flatten().filter(|id| shown_label(id)).cloned().collect();
+
+    SimpleMail {
+        id: mf.id,
+        thread_id: mf.thread_id,
+        subject,
+        from,
+        to,
+        cc,
+        reply_to,
+        date,
+        message_id,
+        in_reply_to,
+        references,
+        snippet: mf.snippet,
+        body,
+        internal_date: mf.internal_date.and_then(|d| d.parse().ok()),
+        has_attachment,
+        unread,
+        starred,
+        size: mf.size_estimate,
+        labels,
+    }
+}
+
 pub fn fetch_latest(access_token: &str, max_results: usize) -> Result<Vec<SimpleMail>, Box<dyn Error + Send + Sync>> {
+    fetch_messages(access_token, &[("labelIds", "INBOX")], max_results)
+}
+
+/// Run a Gmail search query (same syntax as the web UI, e.g. `from:ci@ is:unread newer_than:7d`)
+/// across all mail and fetch the matching messages.
+pub fn search(access_token: &str, query: &str, max_results: usize) -> Result<Vec<SimpleMail>, Box<dyn Error + Send + Sync>> {
+    fetch_messages(access_token, &[("q", query)], max_results)
+}
+
+fn fetch_messages(access_token: &str, params: &[(&str, &str)], max_results: usize) -> Result<Vec<SimpleMail>, Box<dyn Error + Send + Sync>> {
     let client = Client::new();
-    let list_url = format!(
-        "https://gmail.googleapis.com/gmail/v1/users/me/messages?labelIds=INBOX&maxResults={}",
-        max_results
-    );
+    let list_url = "https://gmail.googleapis.com/gmail/v1/users/me/messages";
+    let max = max_results.to_string();
 
     let list_res = client
-        .get(&list_url)
+        .get(list_url)
+        .query(params)
+        .query(&[("maxResults", max.as_str())])
         .bearer_auth(access_token)
         .send()?;
 
@@ -65,41 +276,189 @@ pub fn fetch_latest(access_token: &str, max_results: usize) -> Result<Vec<Simple
     let mut out = Vec::new();
     if let Some(msgs) = list.messages {
         for m in msgs {
-            let msg_url = format!("https://gmail.googleapis.com/gmail/v1/users/me/messages/{}?format=full", m.id);
-            let msg_res = client
-                .get(&msg_url)
-                .bearer_auth(access_token)
-                .send()?;
-            if !msg_res.status().is_success() {
-                let status = msg_res.status();
-                let body = msg_res.text().unwrap_or_else(|_| "<failed to read body>".into());
-                return Err(format!("gmail get message error: {} - {}", status, body).into());
+            out.push(get_message(&client, access_token, &m.id)?);
+        }
+    }
+    Ok(out)
+}
+
+/// Fetch one message by its Gmail id.
+pub fn fetch_message(access_token: &str, id: &str) -> Result<SimpleMail, Box<dyn Error + Send + Sync>> {
+    get_message(&Client::new(), access_token, id)
+}
+
+fn get_message(client: &Client, access_token: &str, id: &str) -> Result<SimpleMail, Box<dyn Error + Send + Sync>> {
+    let msg_url = format!("https://gmail.googleapis.com/gmail/v1/users/me/messages/{}?format=full", id);
+    let msg_res = client
+        .get(&msg_url)
+        .bearer_auth(access_token)
+        .send()?;
+    if !msg_res.status().is_success() {
+        let status = msg_res.status();
+        let body = msg_res.text().unwrap_or_else(|_| "<failed to read body>".into());
+        return Err(format!("gmail get message error: {} - {}", status, body).into());
+    }
+    let mf: MessageFull = msg_res.json()?;
+    Ok(to_simple(mf))
+}
+
+/// Fetch every message of a conversation through the threads endpoint, oldest first.
+pub fn fetch_thread(access_token: &str, thread_id: &str) -> Result<Vec<SimpleMail>, Box<dyn Error + Send + Sync>> {
+    let client = Client::new();
+    let url = format!("https://gmail.googleapis.com/gmail/v1/users/me/threads/{}?format=full", thread_id);
+    let res = client
+        .get(&url)
+        .bearer_auth(access_token)
+        .send()?;
+    if !res.status().is_success() {
+        return Err(api_error("gmail get thread error", res));
+    }
+    let thread: ThreadFull = res.json()?;
+
+    let mut out: Vec<SimpleMail> = thread.messages.unwrap_or_default().into_iter().map(to_simple).collect();
+    out.sort_by_key(|m| m.internal_date.unwrap_or(0));
+    Ok(out)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Profile {
+    email_address: String,
+}
+
+/// Primary address of the authenticated account.
+pub fn fetch_profile_email(access_token: &str) -> Result<String, Box<dyn Error + Send + Sync>> {
+    let client = Client::new();
+    let res = client
+        .get("https://gmail.googleapis.com/gmail/v1/users/me/profile")
+        .bearer_auth(access_token)
+        .send()?;
+    if !res.status().is_success() {
+        let status = res.status();
+        let body = res.text().unwrap_or_else(|_| "<failed to read body>".into());
+        return Err(format!("gmail profile API error: {} - {}", status, body).into());
+    }
+    let profile: Profile = res.json()?;
+    Ok(profile.email_address)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SendAsListResp {
+    send_as: Option<Vec<SendAsResp>>,
+}
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SendAsResp {
+    send_as_email: String,
+    display_name: Option<String>,
+    reply_to_address: Option<String>,
+    signature: Option<String>,
+    is_default: Option<bool>,
+    verification_status: Option<String>,
+}
+
+/// A Gmail "send as" address the account may put in `From:`.
+#[derive(Debug, Clone)]
+pub struct SendAs {
+    pub email: String,
+    pub display_name: Option<String>,
+    pub reply_to: Option<String>,
+    /// plain-text rendering of the (HTML) signature
+    pub signature: Option<String>,
+    pub is_default: bool,
+}
+
+impl SendAs {
+    /// `From:` value, e.g. "Team CI <ci@example.com>".
+    pub fn address(&self) -> String {
+        match self.display_name.as_deref().filter(|n| !n.is_empty()) {
+            Some(name) => format!("{} <{}>", name, self.email),
+            None => self.email.clone(),
+        }
+    }
+}
+
+// Gmail stores signatures as HTML; keep line breaks and text, drop the markup
+fn html_to_text(html: &str) -> String {
+    let mut out = String::new();
+    let mut tag = String::new();
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => {
+                in_tag = true;
+                tag.clear();
+            }
+            '>' if in_tag => {
+                in_tag = false;
+                let name = tag.trim_start_matches('/').split_whitespace().next().unwrap_or("").to_lowercase();
+                if matches!(name.as_str(), "br" | "br/" | "p" | "div" | "li" | "tr") && !out.ends_with('\n') {
+                    out.push('\n');
+                }
             }
-            let mf: MessageFull = msg_res.json()?;
-
-            let subject = header_value(mf.payload.as_ref().and_then(|p| p.headers.as_ref()), "Subject");
-            let from = header_value(mf.payload.as_ref().and_then(|p| p.headers.as_ref()), "From");
-            let date = header_value(mf.payload.as_ref().and_then(|p| p.headers.as_ref()), "Date");
-
-            out.push(SimpleMail {
-                id: mf.id,
-                subject,
-                from,
-                date,
-                snippet: mf.snippet,
-            });
+            _ if in_tag => tag.push(c),
+            _ => out.push(c),
         }
     }
+    out.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+        .trim()
+        .to_string()
+}
+
+/// Verified send-as identities from the account settings, default first.
+pub fn fetch_send_as(access_token: &str) -> Result<Vec<SendAs>, Box<dyn Error + Send + Sync>> {
+    let client = Client::new();
+    let res = client
+        .get("https://gmail.googleapis.com/gmail/v1/users/me/settings/sendAs")
+        .bearer_auth(access_token)
+        .send()?;
+    if !res.status().is_success() {
+        let status = res.status();
+        let body = res.text().unwrap_or_else(|_| "<failed to read body>".into());
+        return Err(format!("gmail sendAs API error: {} - {}", status, body).into());
+    }
+    let list: SendAsListResp = res.json()?;
+
+    let mut out: Vec<SendAs> = list
+        .send_as
+        .unwrap_or_default()
+        .into_iter()
+        // the primary address has no verification status; aliases must be accepted
+        .filter(|s| s.verification_status.as_deref().is_none_or(|v| v == "accepted"))
+        .map(|s| SendAs {
+            email: s.send_as_email,
+            display_name: s.display_name,
+            reply_to: s.reply_to_address.filter(|r| !r.is_empty()),
+            signature: s.signature.map(|h| html_to_text(&h)).filter(|t| !t.is_empty()),
+            is_default: s.is_default.unwrap_or(false),
+        })
+        .collect();
+    out.sort_by_key(|s| !s.is_default);
     Ok(out)
 }
 
-pub fn send_mail(access_token: &str, raw_rfc822: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+/// Send a message produced by `compose::builder`. Pass the original `thread_id` for replies
+/// so Gmail files the message into the same conversation.
+pub fn send_mail(access_token: &str, raw: &RawMessage, thread_id: Option<&str>) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if raw.len() > SIMPLE_SEND_LIMIT {
+        return send_mail_resumable(access_token, raw, thread_id);
+    }
+
     let client = Client::new();
     // Gmail API expects base64url (URL-safe, no padding)
-    let encoded = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(raw_rfc822.as_bytes());
+    let encoded = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(raw.as_bytes());
 
     let send_url = "https://gmail.googleapis.com/gmail/v1/users/me/messages/send";
-    let body = serde_json::json!({ "raw": encoded });
+    let mut body = serde_json::json!({ "raw": encoded });
+    if let Some(tid) = thread_id {
+        body["threadId"] = serde_json::Value::from(tid);
+    }
 
     let res = client
         .post(send_url)
@@ -108,10 +467,232 @@ pub fn send_mail(access_token: &str, raw_rfc822: &str) -> Result<(), Box<dyn Err
         .send()?;
 
     if !res.status().is_success() {
-        let status = res.status();
-        let b = res.text().unwrap_or_else(|_| "<failed to read body>".into());
-        return Err(format!("gmail send API error: {} - {}", status, b).into());
+        return Err(api_error("gmail send API error", res));
+    }
+
+    Ok(())
+}
+
+fn send_mail_resumable(access_token: &str, raw: &RawMessage, thread_id: Option<&str>) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let mut metadata = serde_json::json!({});
+    if let Some(tid) = thread_id {
+        metadata["threadId"] = serde_json::Value::from(tid);
+    }
+    let url = "https://gmail.googleapis.com/upload/gmail/v1/users/me/messages/send?uploadType=resumable";
+    upload_resumable(reqwest::Method::POST, url, access_token, &metadata, raw, "gmail upload")?;
+    Ok(())
+}
+
+// two-step resumable upload: open a session with the metadata, then PUT the message bytes.
+// Returns the final response, which carries the created resource.
+fn upload_resumable(
+    method: reqwest::Method,
+    url: &str,
+    access_token: &str,
+    metadata: &serde_json::Value,
+    raw: &RawMessage,
+    context: &str,
+) -> Result<reqwest::blocking::Response, Box<dyn Error + Send + Sync>> {
+    let client = Client::new();
+    let res = client
+        .request(method, url)
+        .bearer_auth(access_token)
+        .header("X-Upload-Content-Type", "message/rfc822")
+        .header("X-Upload-Content-Length", raw.len().to_string())
+        .json(metadata)
+        .send()?;
+    if !res.status().is_success() {
+        return Err(api_error(&format!("{} session error", context), res));
+    }
+    let session_url = res
+        .headers()
+        .get(reqwest::header::LOCATION)
+        .and_then(|v| v.to_str().ok())
+        .ok_or_else(|| format!("{} session returned no Location", context))?
+        .to_string();
+
+    let res = client
+        .put(&session_url)
+        .bearer_auth(access_token)
+        .header(reqwest::header::CONTENT_TYPE, "message/rfc822")
+        .body(raw.as_bytes().to_vec())
+        .send()?;
+    if !res.status().is_success() {
+        return Err(api_error(&format!("{} error", context), res));
+    }
+    Ok(res)
+}
+
+/// A saved Gmail draft and the message it holds.
+#[derive(Debug, Clone)]
+pub struct GmailDraft {
+    pub id: String,
+    pub message: SimpleMail,
+    /// drafts keep their Bcc header, unlike sent messages, so it is not part of `SimpleMail`
+    pub bcc: Option<String>,
+    /// files attached to the draft; `fetch_attachment` gets their content
+    pub attachments: Vec<DraftAttachment>,
+}
+
+/// A file attached to a saved message. Small ones come inline, large ones by id.
+#[derive(Debug, Clone)]
+pub struct DraftAttachment {
+    pub filename: String,
+    attachment_id: Option<String>,
+    // base64url, when Gmail sent the content along
+    data: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct AttachmentBody {
+    data: String,
+}
+
+fn decode_base64url(data: &str) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+    Ok(base64::engine::general_purpose::URL_SAFE_NO_PAD.decode(data.trim_end_matches('='))?)
+}
+
+/// Content of an attachment of message `message_id`.
+pub fn fetch_attachment(access_token: &str, message_id: &str, a: &DraftAttachment) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+    if let Some(data) = &a.data {
+        return decode_base64url(data);
+    }
+    let id = a.attachment_id.as_deref().ok_or_else(|| format!("attachment {} has no content", a.filename))?;
+    let url = format!("https://gmail.googleapis.com/gmail/v1/users/me/messages/{}/attachments/{}", message_id, id);
+    let res = Client::new().get(&url).bearer_auth(access_token).send()?;
+    if !res.status().is_success() {
+        return Err(api_error("gmail attachments.get error", res));
+    }
+    let body: AttachmentBody = res.json()?;
+    decode_base64url(&body.data)
+}
+
+fn draft_message_json(raw: &RawMessage, thread_id: Option<&str>) -> serde_json::Value {
+    let encoded = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(raw.as_bytes());
+    let mut message = serde_json::json!({ "raw": encoded });
+    if let Some(tid) = thread_id {
+        message["threadId"] = serde_json::Value::from(tid);
     }
+    serde_json::json!({ "message": message })
+}
+
+// the draft resource without its content, for the upload endpoints
+fn draft_metadata(draft_id: Option<&str>, thread_id: Option<&str>) -> serde_json::Value {
+    let mut message = serde_json::json!({});
+    if let Some(tid) = thread_id {
+        message["threadId"] = serde_json::Value::from(tid);
+    }
+    let mut body = serde_json::json!({ "message": message });
+    if let Some(id) = draft_id {
+        body["id"] = serde_json::Value::from(id);
+    }
+    body
+}
+
+/// Save a new draft (`drafts.create`) and return its draft id. Large messages go
+/// through the upload endpoint, as in `send_mail`.
+pub fn create_draft(access_token: &str, raw: &RawMessage, thread_id: Option<&str>) -> Result<String, Box<dyn Error + Send + Sync>> {
+    if raw.len() > SIMPLE_SEND_LIMIT {
+        let url = "https://gmail.googleapis.com/upload/gmail/v1/users/me/drafts?uploadType=resumable";
+        let res = upload_resumable(reqwest::Method::POST, url, access_token, &draft_metadata(None, thread_id), raw, "gmail drafts.create upload")?;
+        let created: DraftRef = res.json()?;
+        return Ok(created.id);
+    }
+    let client = Client::new();
+    let res = client
+        .post("https://gmail.googleapis.com/gmail/v1/users/me/drafts")
+        .bearer_auth(access_token)
+        .json(&draft_message_json(raw, thread_id))
+        .send()?;
+    if !res.status().is_success() {
+        return Err(api_error("gmail drafts.create error", res));
+    }
+    let created: DraftRef = res.json()?;
+    Ok(created.id)
+}
+
+/// Replace the content of an existing draft (`drafts.update`).
+pub fn update_draft(access_token: &str, draft_id: &str, raw: &RawMessage, thread_id: Option<&str>) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let url = format!("https://gmail.googleapis.com/gmail/v1/users/me/drafts/{}", draft_id);
+    if raw.len() > SIMPLE_SEND_LIMIT {
+        let url = format!("https://gmail.googleapis.com/upload/gmail/v1/users/me/drafts/{}?uploadType=resumable", draft_id);
+        let metadata = draft_metadata(Some(draft_id), thread_id);
+        upload_resumable(reqwest::Method::PUT, &url, access_token, &metadata, raw, "gmail drafts.update upload")?;
+        return Ok(());
+    }
+    let client = Client::new();
+    let mut body = draft_message_json(raw, thread_id);
+    body["id"] = serde_json::Value::from(draft_id);
+    let res = client
+        .put(&url)
+        .bearer_auth(access_token)
+        .json(&body)
+        .send()?;
+    if !res.status().is_success() {
+        return Err(api_error("gmail drafts.update error", res));
+    }
+    Ok(())
+}
+
+/// List drafts with their full message content.
+pub fn list_drafts(access_token: &str, max_results: usize) -> Result<Vec<GmailDraft>, Box<dyn Error + Send + Sync>> {
+    let client = Client::new();
+    let max = max_results.to_string();
+    let res = client
+        .get("https://gmail.googleapis.com/gmail/v1/users/me/drafts")
+        .query(&[("maxResults", max.as_str())])
+        .bearer_auth(access_token)
+        .send()?;
+    if !res.status().is_success() {
+        return Err(api_error("gmail drafts.list error", res));
+    }
+    let list: DraftsListResp = res.json()?;
+
+    let mut out = Vec::new();
+    for d in list.drafts.unwrap_or_default() {
+        let url = format!("https://gmail.googleapis.com/gmail/v1/users/me/drafts/{}?format=full", d.id);
+        let res = client
+            .get(&url)
+            .bearer_auth(access_token)
+            .send()?;
+        if !res.status().is_success() {
+            return Err(api_error("gmail drafts.get error", res));
+        }
+        let full: DraftFull = res.json()?;
+        let mut attachments = Vec::new();
+        if let Some(p) = &full.message.payload {
+            attachment_parts(p, &mut attachments);
+        }
+        let bcc = header_value(full.message.payload.as_ref().and_then(|p| p.headers.as_ref()), "Bcc");
+        out.push(GmailDraft { id: full.id, message: to_simple(full.message), bcc, attachments });
+    }
+    Ok(out)
+}
 
+/// Send a saved draft (`drafts.send`); Gmail removes the draft once sent.
+pub fn send_draft(access_token: &str, draft_id: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let client = Client::new();
+    let res = client
+        .post("https://gmail.googleapis.com/gmail/v1/users/me/drafts/send")
+        .bearer_auth(access_token)
+        .json(&serde_json::json!({ "id": draft_id }))
+        .send()?;
+    if !res.status().is_success() {
+        return Err(api_error("gmail drafts.send error", res));
+    }
+    Ok(())
+}
+
+/// Discard a draft (`drafts.delete`).
+pub fn delete_draft(access_token: &str, draft_id: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let client = Client::new();
+    let url = format!("https://gmail.googleapis.com/gmail/v1/users/me/drafts/{}", draft_id);
+    let res = client
+        .delete(&url)
+        .bearer_auth(access_token)
+        .send()?;
+    if !res.status().is_success() {
+        return Err(api_error("gmail drafts.delete error", res));
+    }
     Ok(())
 }
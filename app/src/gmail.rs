@@ -1,2 +1 @@
 pub use crate::fetch::gmail::*;
-pub use crate::fetch::gmail::*;
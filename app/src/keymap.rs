@@ -0,0 +1,541 @@
+//! Key chords and sequences mapped to named actions, per view.
+//!
+//! A key is a single character, a name (enter, esc, tab, backspace, space, up, down, left,
+//! right, pageup, pagedown, home, end, f1..f12) or either of those with modifiers:
+//! `C-d` (Ctrl), `M-v` (Alt), `S-tab` (Shift). A sequence is keys separated by spaces: `g g`,
+//! `C-x C-c`. A binding that is a prefix of a longer one is shadowed by it. Binding a key to
+//! two actions of the same view is a config error. In the composer typed characters are text,
+//! so only bindings that start with another key (`C-s`, `tab`, `f1`, ...) apply there.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Chord {
+    code: KeyCode,
+    mods: KeyModifiers,
+}
+
+impl From<KeyEvent> for Chord {
+    fn from(key: KeyEvent) -> Self {
+        let mut mods = key.modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT);
+        // the character already says whether Shift was held
+        match key.code {
+            KeyCode::Char(_) => mods.remove(KeyModifiers::SHIFT),
+            KeyCode::BackTab => mods.insert(KeyModifiers::SHIFT),
+            _ => {}
+        }
+        Chord { code: key.code, mods }
+    }
+}
+
+fn parse_code(s: &str) -> Option<KeyCode> {
+    let mut chars = s.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(KeyCode::Char(c));
+    }
+    let lower = s.to_lowercase();
+    if let Some(n) = lower.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()).filter(|n| (1..=12).contains(n)) {
+        return Some(KeyCode::F(n));
+    }
+    Some(match lower.as_str() {
+        "enter" => KeyCode::Enter,
+        "esc" => KeyCode::Esc,
+        "tab" => KeyCode::Tab,
+        "backspace" => KeyCode::Backspace,
+        "space" => KeyCode::Char(' '),
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "delete" => KeyCode::Delete,
+        _ => return None,
+    })
+}
+
+impl Chord {
+    pub fn parse(s: &str) -> Option<Chord> {
+        let mut mods = KeyModifiers::NONE;
+        let mut rest = s;
+        // "C-x", "M-S-tab"; a lone "-" or "C--" is the minus key itself
+        while rest.len() > 2 && rest.as_bytes()[1] == b'-' {
+            match rest.as_bytes()[0] {
+                b'C' => mods |= KeyModifiers::CONTROL,
+                b'M' | b'A' => mods |= KeyModifiers::ALT,
+                b'S' => mods |= KeyModifiers::SHIFT,
+                _ => break,
+            }
+            rest = &rest[2..];
+        }
+        let mut code = parse_code(rest)?;
+        if let KeyCode::Char(c) = code
+            && mods.contains(KeyModifiers::SHIFT)
+        {
+            code = KeyCode::Char(c.to_ascii_uppercase());
+            mods.remove(KeyModifiers::SHIFT);
+        }
+        if code == KeyCode::Tab && mods.contains(KeyModifiers::SHIFT) {
+            code = KeyCode::BackTab;
+        }
+        Some(Chord { code, mods })
+    }
+}
+
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.mods.contains(KeyModifiers::CONTROL) {
+            write!(f, "C-")?;
+        }
+        if self.mods.contains(KeyModifiers::ALT) {
+            write!(f, "M-")?;
+        }
+        if self.mods.contains(KeyModifiers::SHIFT) {
+            write!(f, "S-")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(n) => write!(f, "f{}", n),
+            KeyCode::BackTab => write!(f, "tab"),
+            KeyCode::PageUp => write!(f, "pageup"),
+            KeyCode::PageDown => write!(f, "pagedown"),
+            other => write!(f, "{}", format!("{:?}", other).to_lowercase()),
+        }
+    }
+}
+
+pub type Sequence = Vec<Chord>;
+
+fn parse_sequence(s: &str) -> Option<Sequence> {
+    let seq: Option<Sequence> = s.split_whitespace().map(Chord::parse).collect();
+    seq.filter(|s| !s.is_empty())
+}
+
+fn sequence_name(seq: &Sequence) -> String {
+    seq.iter().map(|c| c.to_string()).collect::<Vec<_>>().join(" ")
+}
+
+/// The views that have their own set of bindings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Context {
+    List,
+    Reader,
+    Thread,
+    Drafts,
+    Contacts,
+    Outbox,
+    MessageLog,
+    Review,
+    Composer,
+}
+
+impl Context {
+    pub const ALL: &[Context] = &[
+        Context::List,
+        Context::Reader,
+        Context::Thread,
+        Context::Drafts,
+        Context::Contacts,
+        Context::Outbox,
+        Context::MessageLog,
+        Context::Review,
+        Context::Composer,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Context::List => "the message list",
+            Context::Reader => "the reader",
+            Context::Thread => "the thread view",
+            Context::Drafts => "drafts",
+            Context::Contacts => "contacts",
+            Context::Outbox => "the outbox",
+            Context::MessageLog => "the message log",
+            Context::Review => "the review screen",
+            Context::Composer => "the composer",
+        }
+    }
+
+    // whether `seq` can fire here; a plain character is text in the composer
+    fn accepts(self, seq: &Sequence) -> bool {
+        self != Context::Composer
+            || seq.first().is_none_or(|c| !matches!(c.code, KeyCode::Char(_)) || c.mods.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT))
+    }
+}
+
+macro_rules! actions {
+    ($($variant:ident $name:literal [$($ctx:ident),*] $desc:literal,)*) => {
+        /// Something a key can do; the name is the key in `[keybindings]`.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub enum Action {
+            $($variant,)*
+        }
+
+        impl Action {
+            pub const ALL: &[Action] = &[$(Action::$variant,)*];
+
+            pub fn name(self) -> &'static str {
+                match self {
+                    $(Action::$variant => $name,)*
+                }
+            }
+
+            pub fn description(self) -> &'static str {
+                match self {
+                    $(Action::$variant => $desc,)*
+                }
+            }
+
+            fn contexts(self) -> &'static [Context] {
+                match self {
+                    $(Action::$variant => &[$(Context::$ctx),*],)*
+                }
+            }
+        }
+    };
+}
+
+actions! {
+    Quit "quit" [List, Reader, Thread, Drafts, Contacts, Outbox, MessageLog] "quit (elsewhere: back to the list)",
+    Back "back" [List, Reader, Thread, Drafts, Contacts, Outbox, MessageLog] "leave the search results, or go back",
+    Help "help" [List, Reader, Thread, Drafts, Contacts, Outbox, MessageLog, Review, Composer] "show this help",
+    Up "up" [List, Reader, Thread, Drafts, Contacts, Outbox, MessageLog] "move up",
+    Down "down" [List, Reader, Thread, Drafts, Contacts, Outbox, MessageLog] "move down",
+    PageUp "page_up" [List, Reader, Thread, Drafts, Contacts, Outbox, MessageLog] "page up",
+    PageDown "page_down" [List, Reader, Thread, Drafts, Contacts, Outbox, MessageLog] "page down",
+    Top "top" [List, Reader, Thread, Drafts, Contacts, Outbox, MessageLog] "go to the top",
+    Bottom "bottom" [List, Reader, Thread, Drafts, Contacts, Outbox, MessageLog] "go to the bottom",
+    Search "search" [List, Reader] "search",
+    Open "open" [List, Thread, Drafts, Outbox] "open: read, expand a thread message, resume a draft, edit a queued one",
+    ExpandAll "expand_all" [Thread] "expand or collapse every message",
+    Add "add" [Contacts] "add",
+    Edit "edit" [Contacts, Outbox, Review] "edit",
+    Delete "delete" [Drafts, Contacts, Outbox] "delete",
+    Retry "retry" [Outbox] "retry now",
+    Import "import" [Contacts] "import a .vcf or mutt alias file",
+    ToggleThreads "toggle_threads" [List] "switch between messages and threads",
+    Compose "compose" [List] "write a new message",
+    Reply "reply" [List] "reply",
+    ReplyAll "reply_all" [List] "reply to all",
+    Forward "forward" [List] "forward",
+    Drafts "drafts" [List] "saved drafts",
+    Contacts "contacts" [List] "address book",
+    Outbox "outbox" [List] "messages waiting to be sent",
+    MessageLog "message_log" [List] "notices and errors",
+    TogglePreview "toggle_preview" [List] "show or hide the preview pane",
+    GrowPreview "grow_preview" [List] "make the preview larger",
+    ShrinkPreview "shrink_preview" [List] "make the preview smaller",
+    NextMatch "next_match" [Reader] "next search match",
+    PrevMatch "prev_match" [Reader] "previous search match",
+    NextMessage "next_message" [Reader] "next message (when not searching)",
+    PrevMessage "prev_message" [Reader] "previous message",
+    Send "send" [Review] "send",
+    SaveDraft "save_draft" [Review] "save as a Gmail draft",
+    Discard "discard" [Review] "discard the message (asks first)",
+    ChangeFrom "change_from" [Review] "switch to the next From identity",
+    Attach "attach" [Review] "attach a file",
+    Done "done" [Composer] "finish editing and review the message",
+    Undo "undo" [Composer] "undo",
+    NextField "next_field" [Composer] "next field",
+    PrevField "prev_field" [Composer] "previous field",
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Preset {
+    #[default]
+    Default,
+    Vim,
+    Emacs,
+}
+
+impl Preset {
+    fn keys(self, action: Action) -> &'static [&'static str] {
+        use Action::*;
+        match (self, action) {
+            (Preset::Vim, Top) => &["home", "g g"],
+            (Preset::Vim, PageUp) => &["pageup", "C-u", "C-b"],
+            (Preset::Vim, PageDown) => &["pagedown", "C-d", "C-f"],
+            (Preset::Emacs, Quit) => &["q", "C-x C-c"],
+            (Preset::Emacs, Back) => &["esc", "C-g"],
+            (Preset::Emacs, Up) => &["up", "C-p"],
+            (Preset::Emacs, Down) => &["down", "C-n"],
+            (Preset::Emacs, PageUp) => &["pageup", "M-v"],
+            (Preset::Emacs, PageDown) => &["pagedown", "C-v"],
+            (Preset::Emacs, Top) => &["home", "M-<"],
+            (Preset::Emacs, Bottom) => &["end", "M->"],
+            (Preset::Emacs, Search) => &["/", "C-s"],
+            (_, Quit) => &["q"],
+            (_, Back) => &["esc"],
+            (_, Help) => &["?", "f1"],
+            (_, Up) => &["up", "k"],
+            (_, Down) => &["down", "j"],
+            (_, PageUp) => &["pageup", "b"],
+            (_, PageDown) => &["pagedown", "space"],
+            (_, Top) => &["home", "g"],
+            (_, Bottom) => &["end", "G"],
+            (_, Search) => &["/"],
+            (_, Open) => &["enter"],
+            (_, ExpandAll) => &["e"],
+            (_, Add) => &["a"],
+            (_, Edit) => &["e"],
+            (_, Delete) => &["d"],
+            (_, Retry) => &["r"],
+            (_, Import) => &["i"],
+            (_, ToggleThreads) => &["t"],
+            (_, Compose) => &["c"],
+            (_, Reply) => &["r"],
+            (_, ReplyAll) => &["a"],
+            (_, Forward) => &["f"],
+            (_, Drafts) => &["D"],
+            (_, Contacts) => &["C"],
+            (_, Outbox) => &["o"],
+            (_, MessageLog) => &["L"],
+            (_, TogglePreview) => &["p"],
+            (_, GrowPreview) => &["+"],
+            (_, ShrinkPreview) => &["-"],
+            (_, NextMatch) => &["n"],
+            (_, PrevMatch) => &["N"],
+            (_, NextMessage) => &["n"],
+            (_, PrevMessage) => &["p"],
+            (_, Send) => &["s"],
+            (_, SaveDraft) => &["d"],
+            (_, Discard) => &["x"],
+            (_, ChangeFrom) => &["f"],
+            (_, Attach) => &["a"],
+            (_, Done) => &["C-s", "C-x", "esc"],
+            (_, Undo) => &["C-z", "C-u"],
+            (_, NextField) => &["tab"],
+            (_, PrevField) => &["S-tab"],
+        }
+    }
+}
+
+/// `[keybindings]`: `preset = "vim"` (or "emacs", "default"), plus per-action overrides that
+/// replace the preset's keys, e.g. `compose = "m"` or `down = ["j", "C-n"]`.
+#[derive(Deserialize, Debug)]
+#[serde(try_from = "BTreeMap<String, KeySpec>")]
+pub struct Keymap {
+    bindings: Vec<(Action, Vec<Sequence>)>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum KeySpec {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Keymap {
+    pub fn preset(preset: Preset) -> Keymap {
+        let bindings = Action::ALL
+            .iter()
+            .map(|a| (*a, preset.keys(*a).iter().filter_map(|k| parse_sequence(k)).collect()))
+            .collect();
+        Keymap { bindings }
+    }
+
+    /// The keys for `action` that work in `context`, as written in the config; empty when
+    /// it is unbound there.
+    pub fn keys_for(&self, action: Action, context: Context) -> Vec<String> {
+        self.bindings
+            .iter()
+            .filter(|(a, _)| *a == action)
+            .flat_map(|(_, seqs)| seqs.iter().filter(|s| context.accepts(s)).map(sequence_name))
+            .collect()
+    }
+
+    /// Bound actions of a view with their keys, for the help overlay.
+    pub fn help(&self, context: Context) -> Vec<(String, &'static str)> {
+        self.bindings
+            .iter()
+            .filter(|(a, _)| a.contexts().contains(&context))
+            .map(|(a, _)| (self.keys_for(*a, context).join(", "), a.description()))
+            .filter(|(keys, _)| !keys.is_empty())
+            .collect()
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap::preset(Preset::Default)
+    }
+}
+
+impl TryFrom<BTreeMap<String, KeySpec>> for Keymap {
+    type Error = String;
+
+    fn try_from(mut table: BTreeMap<String, KeySpec>) -> Result<Self, String> {
+        let preset = match table.remove("preset") {
+            None => Preset::Default,
+            Some(KeySpec::One(p)) => match p.as_str() {
+                "default" => Preset::Default,
+                "vim" => Preset::Vim,
+                "emacs" => Preset::Emacs,
+                other => return Err(format!("unknown preset {:?}, expected \"default\", \"vim\" or \"emacs\"", other)),
+            },
+            Some(KeySpec::Many(_)) => return Err("preset must be a string".into()),
+        };
+        let mut keymap = Keymap::preset(preset);
+        let overridden: Vec<String> = table.keys().cloned().collect();
+        for (name, spec) in table {
+            let action = Action::ALL
+                .iter()
+                .find(|a| a.name() == name)
+                .ok_or_else(|| format!("unknown action {:?}", name))?;
+            let keys = match spec {
+                KeySpec::One(k) => vec![k],
+                KeySpec::Many(ks) => ks,
+            };
+            let seqs = keys
+                .iter()
+                .map(|k| parse_sequence(k).ok_or_else(|| format!("invalid key {:?} for {}", k, name)))
+                .collect::<Result<Vec<_>, _>>()?;
+            if let Some(entry) = keymap.bindings.iter_mut().find(|(a, _)| a == action) {
+                entry.1 = seqs;
+            }
+        }
+        keymap.check_conflicts(&overridden)?;
+        Ok(keymap)
+    }
+}
+
+// actions meant to share a key: the view does the first one that applies
+fn may_share(a: Action, b: Action) -> bool {
+    use Action::*;
+    matches!((a, b), (NextMatch, NextMessage) | (NextMessage, NextMatch) | (PrevMatch, PrevMessage) | (PrevMessage, PrevMatch))
+}
+
+impl Keymap {
+    // the same sequence on two actions of a view would leave one of them unreachable;
+    // `overridden` names the actions set in the config, to point at the likely culprit
+    fn check_conflicts(&self, overridden: &[String]) -> Result<(), String> {
+        for context in Context::ALL {
+            let bound: Vec<(Action, &Sequence)> = self
+                .bindings
+                .iter()
+                .filter(|(a, _)| a.contexts().contains(context))
+                .flat_map(|(a, seqs)| seqs.iter().filter(|s| context.accepts(s)).map(move |s| (*a, s)))
+                .collect();
+            for (i, (a, seq)) in bound.iter().enumerate() {
+                let Some((b, _)) = bound[i + 1..].iter().find(|(b, other)| b != a && other == seq && !may_share(*a, *b)) else {
+                    continue;
+                };
+                let (first, second) = if overridden.iter().any(|o| o == b.name()) { (b, a) } else { (a, b) };
+                return Err(format!(
+                    "key {:?} is bound to both {} and {} in {}; rebind {} as well",
+                    sequence_name(seq),
+                    first.name(),
+                    second.name(),
+                    context.name(),
+                    second.name()
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Collects the keys of a sequence as they are typed.
+#[derive(Default)]
+pub struct Dispatcher {
+    pending: Sequence,
+}
+
+impl Dispatcher {
+    /// Feed a key press. Returns the actions bound to the sequence it completes, in binding
+    /// order; nothing while a longer sequence is still being typed or when the keys aren't bound.
+    pub fn feed(&mut self, keymap: &Keymap, context: Context, key: KeyEvent) -> Vec<Action> {
+        let chord = Chord::from(key);
+        if !self.pending.is_empty() && chord.code == KeyCode::Esc {
+            self.pending.clear();
+            return Vec::new();
+        }
+        self.pending.push(chord);
+
+        let mut exact = Vec::new();
+        let mut longer = false;
+        for (action, seqs) in keymap.bindings.iter().filter(|(a, _)| a.contexts().contains(&context)) {
+            for seq in seqs.iter().filter(|s| context.accepts(s) && s.starts_with(&self.pending)) {
+                if seq.len() == self.pending.len() {
+                    exact.push(*action);
+                } else {
+                    longer = true;
+                }
+            }
+        }
+        if longer {
+            return Vec::new();
+        }
+        let started_fresh = self.pending.len() == 1;
+        self.pending.clear();
+        if exact.is_empty() && !started_fresh {
+            // a broken sequence: the last key may start something on its own
+            return self.feed(keymap, context, key);
+        }
+        exact
+    }
+
+    /// Keys typed so far of an unfinished sequence, e.g. for the status line.
+    pub fn pending(&self) -> Option<String> {
+        (!self.pending.is_empty()).then(|| sequence_name(&self.pending))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyEvent;
+
+    fn keymap(toml: &str) -> Result<Keymap, String> {
+        let table: BTreeMap<String, KeySpec> = toml::from_str(toml).map_err(|e| e.to_string())?;
+        Keymap::try_from(table)
+    }
+
+    fn press(c: char) -> KeyEvent {
+        KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn presets_have_no_conflicts() {
+        for preset in [Preset::Default, Preset::Vim, Preset::Emacs] {
+            assert_eq!(Keymap::preset(preset).check_conflicts(&[]), Ok(()), "{:?}", preset);
+        }
+    }
+
+    #[test]
+    fn a_key_taken_from_another_action_is_an_error() {
+        let err = keymap("compose = \"r\"").unwrap_err();
+        assert!(err.contains("compose") && err.contains("reply"), "{}", err);
+        assert!(keymap("compose = \"r\"\nreply = \"R\"").is_ok());
+        // different views may reuse a key
+        assert!(keymap("delete = \"c\"").is_ok());
+    }
+
+    #[test]
+    fn sequences_complete_per_view() {
+        let keys = keymap("preset = \"vim\"").unwrap();
+        let mut d = Dispatcher::default();
+        assert!(d.feed(&keys, Context::Drafts, press('g')).is_empty());
+        assert_eq!(d.feed(&keys, Context::Drafts, press('g')), vec![Action::Top]);
+        assert_eq!(d.feed(&keys, Context::Outbox, press('r')), vec![Action::Retry]);
+        assert_eq!(d.feed(&keys, Context::List, press('r')), vec![Action::Reply]);
+        assert_eq!(d.feed(&keys, Context::Review, press('x')), vec![Action::Discard]);
+    }
+
+    #[test]
+    fn typed_characters_stay_text_in_the_composer() {
+        let keys = Keymap::default();
+        let mut d = Dispatcher::default();
+        assert!(d.feed(&keys, Context::Composer, press('?')).is_empty());
+        assert_eq!(d.feed(&keys, Context::Composer, KeyEvent::new(KeyCode::F(1), KeyModifiers::NONE)), vec![Action::Help]);
+        assert_eq!(d.feed(&keys, Context::Composer, KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL)), vec![Action::Done]);
+        assert_eq!(keys.keys_for(Action::Help, Context::Composer), vec!["f1"]);
+        assert_eq!(keys.keys_for(Action::Help, Context::List), vec!["?", "f1"]);
+    }
+}
@@ -0,0 +1,203 @@
+//! Leveled diagnostics written to a rotating file, so background threads never write
+//! over the TUI. Secrets are redacted before anything reaches the disk.
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::storage::state_dir;
+
+// rotate at this size, keeping mailtui.log.1 .. mailtui.log.KEEP
+const MAX_BYTES: u64 = 1024 * 1024;
+const KEEP: usize = 5;
+
+// values following these keys are secrets; each key lists the separators that may follow it
+const SECRET_KEYS: &[(&str, &str)] = &[
+    ("access_token", "\"': ="),
+    ("refresh_token", "\"': ="),
+    ("id_token", "\"': ="),
+    ("client_secret", "\"': ="),
+    ("code", "="),
+    ("bearer", " "),
+];
+// prefixes of Google access and refresh tokens, redacted wherever they show up
+const TOKEN_PREFIXES: &[&str] = &["ya29.", "1//"];
+
+struct FileLogger {
+    level: LevelFilter,
+    path: PathBuf,
+    file: Mutex<File>,
+}
+
+pub fn default_log_file() -> PathBuf {
+    state_dir().join("mailtui.log")
+}
+
+/// Install the logger. Returns the log file path.
+pub fn init(level: LevelFilter, path: Option<PathBuf>) -> io::Result<PathBuf> {
+    let path = path.unwrap_or_else(default_log_file);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let file = open(&path)?;
+    let logger = FileLogger { level, path: path.clone(), file: Mutex::new(file) };
+    log::set_boxed_logger(Box::new(logger)).map_err(io::Error::other)?;
+    log::set_max_level(level);
+
+    // a panic in raw mode is easy to miss on screen; keep a copy in the log
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        log::error!("panic: {}", info);
+        default_hook(info);
+    }));
+    Ok(path)
+}
+
+fn open(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+fn rotated(path: &Path, n: usize) -> PathBuf {
+    let mut p = path.as_os_str().to_owned();
+    p.push(format!(".{}", n));
+    PathBuf::from(p)
+}
+
+impl FileLogger {
+    // mailtui.log -> .1 -> .2 ... dropping the oldest, then start a fresh file
+    fn rotate(&self, file: &mut File) -> io::Result<()> {
+        let _ = fs::remove_file(rotated(&self.path, KEEP));
+        for n in (1..KEEP).rev() {
+            let _ = fs::rename(rotated(&self.path, n), rotated(&self.path, n + 1));
+        }
+        fs::rename(&self.path, rotated(&self.path, 1))?;
+        *file = open(&self.path)?;
+        Ok(())
+    }
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        // dependencies only get to say something when it's a warning or worse
+        let ours = metadata.target().starts_with(env!("CARGO_CRATE_NAME"));
+        metadata.level() <= self.level && (ours || metadata.level() <= Level::Warn)
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let line = format!(
+            "{} {:<5} {}: {}\n",
+            chrono::Local::now().format("%Y-%m-%dT%H:%M:%S%.3f%:z"),
+            record.level(),
+            record.target(),
+            redact(&record.args().to_string())
+        );
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        if file.metadata().is_ok_and(|m| m.len() + line.len() as u64 > MAX_BYTES) {
+            let _ = self.rotate(&mut file);
+        }
+        let _ = file.write_all(line.as_bytes());
+    }
+
+    fn flush(&self) {
+        let _ = self.file.lock().unwrap_or_else(|e| e.into_inner()).flush();
+    }
+}
+
+fn is_token_char(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b"._~+/=-".contains(&b)
+}
+
+/// Replace token values, auth codes and client secrets with `[redacted]`.
+pub fn redact(msg: &str) -> String {
+    let lower = msg.to_ascii_lowercase();
+    let bytes = msg.as_bytes();
+    // byte ranges to blank out; ASCII-only matching keeps them on char boundaries
+    let mut spans: Vec<(usize, usize)> = Vec::new();
+    let value_end = |start: usize| start + bytes[start..].iter().take_while(|b| is_token_char(**b)).count();
+
+    for (key, seps) in SECRET_KEYS {
+        for (pos, _) in lower.match_indices(key) {
+            let boundary = pos == 0 || !(bytes[pos - 1].is_ascii_alphanumeric() || bytes[pos - 1] == b'_');
+            let after = pos + key.len();
+            let sep_len = bytes[after..].iter().take_while(|b| seps.as_bytes().contains(b)).count();
+            if boundary && sep_len > 0 {
+                let start = after + sep_len;
+                let end = value_end(start);
+                if end > start {
+                    spans.push((start, end));
+                }
+            }
+        }
+    }
+    for prefix in TOKEN_PREFIXES {
+        for (pos, _) in msg.match_indices(prefix) {
+            if pos == 0 || !is_token_char(bytes[pos - 1]) {
+                spans.push((pos, value_end(pos)));
+            }
+        }
+    }
+    if spans.is_empty() {
+        return msg.to_string();
+    }
+
+    spans.sort();
+    let mut out = String::with_capacity(msg.len());
+    let mut last = 0;
+    for (start, end) in spans {
+        if start < last {
+            continue;
+        }
+        out.push_str(&msg[last..start]);
+        out.push_str("[redacted]");
+        last = end;
+    }
+    out.push_str(&msg[last..]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::redact;
+
+    #[test]
+    fn query_string_tokens() {
+        assert_eq!(
+            redact("GET https://example.com/api?access_token=ya29.a0AfH6SM-x_y&alt=json"),
+            "GET https://example.com/api?access_token=[redacted]&alt=json"
+        );
+    }
+
+    #[test]
+    fn json_tokens() {
+        assert_eq!(
+            redact(r#"{"refresh_token": "1//0gAbC-dEf", "expires_in": 3599}"#),
+            r#"{"refresh_token": "[redacted]", "expires_in": 3599}"#
+        );
+    }
+
+    #[test]
+    fn bearer_headers() {
+        assert_eq!(redact("Authorization: Bearer ya29.a0AfH6SMBx"), "Authorization: Bearer [redacted]");
+        // a token on its own is recognized by its prefix
+        assert_eq!(redact("token ya29.a0AfH6SMBx expired"), "token [redacted] expired");
+    }
+
+    #[test]
+    fn auth_codes_in_callback_urls() {
+        assert_eq!(
+            redact("callback http://127.0.0.1:8080/?state=xyz&code=4/0AX4XfWh-Q&scope=mail"),
+            "callback http://127.0.0.1:8080/?state=xyz&code=[redacted]&scope=mail"
+        );
+    }
+
+    #[test]
+    fn lookalike_keys_are_kept() {
+        assert_eq!(redact("status_code=200 errcode=5"), "status_code=200 errcode=5");
+        assert_eq!(redact("no secrets here"), "no secrets here");
+    }
+}
@@ -5,10 +5,69 @@ mod fetch;
 mod token_store;
 mod gmail;
 mod app;
+mod cli;
+mod compose;
+mod config;
+mod events;
+mod keymap;
+mod logging;
+mod sendmail;
+mod theme;
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let _ = dotenv::from_filename("app/.env").or_else(|_| dotenv::dotenv());
+use clap::Parser;
 
-    app::run()?;
-    Ok(())
+fn main() {
+    let mut args: Vec<std::ffi::OsString> = std::env::args_os().collect();
+    // installed or symlinked as `sendmail`: behave like `mailtui sendmail`
+    let as_sendmail = args
+        .first()
+        .and_then(|a| std::path::Path::new(a).file_name())
+        .is_some_and(|n| n == "sendmail");
+    if as_sendmail {
+        args.insert(1, "sendmail".into());
+    } else {
+        cli::expand_mailto(&mut args);
+    }
+    let cli = match cli::Cli::try_parse_from(&args) {
+        Ok(cli) => cli,
+        // sendmail callers expect EX_USAGE for bad arguments, not clap's 2
+        Err(e) if e.use_stderr() && args.iter().any(|a| a == "sendmail") => {
+            let _ = e.print();
+            std::process::exit(sendmail::EX_USAGE);
+        }
+        Err(e) => e.exit(),
+    };
+
+    // a .env next to the config file, then one in (or above) the working directory
+    let _ = dotenv::from_path(storage::config_dir().join(".env"));
+    let _ = dotenv::dotenv();
+    let mut cfg = match config::Config::load(cli.config.as_deref()) {
+        Ok(cfg) => cfg,
+        Err(e) => fail(&format!("invalid configuration: {}", e)),
+    };
+    if let Some(name) = &cli.account
+        && let Err(e) = cfg.select_account(name)
+    {
+        fail(&e);
+    }
+    config::init(cfg);
+
+    let log = &config::get().log;
+    let log_file = cli.log_file.clone().or_else(|| log.file.clone());
+    match logging::init(cli.log_level.unwrap_or(log.level), log_file.clone()) {
+        Ok(path) => log::info!("mailtui {} started, logging to {}", env!("CARGO_PKG_VERSION"), path.display()),
+        // an explicitly chosen log file must work; the default one is best effort
+        Err(e) if log_file.is_some() => fail(&format!("can't open log file: {}", e)),
+        Err(_) => {}
+    }
+
+    if let Err(e) = cli::run(cli) {
+        fail(&e.to_string());
+    }
+}
+
+fn fail(msg: &str) -> ! {
+    log::error!("{}", msg);
+    eprintln!("mailtui: {}", msg);
+    std::process::exit(1);
 }
@@ -0,0 +1,311 @@
+//! `mailtui sendmail`: a sendmail(8) stand-in for cron, git send-email and friends.
+//! Also used when the binary is invoked as `sendmail` (e.g. through a symlink).
+
+use clap::Args;
+use std::error::Error;
+use std::io::Read;
+
+use crate::compose::{addr_spec, split_addresses};
+use crate::gmail::ApiError;
+
+// sysexits.h
+const EX_OK: i32 = 0;
+pub const EX_USAGE: i32 = 64;
+const EX_DATAERR: i32 = 65;
+const EX_UNAVAILABLE: i32 = 69;
+const EX_SOFTWARE: i32 = 70;
+const EX_IOERR: i32 = 74;
+const EX_TEMPFAIL: i32 = 75;
+const EX_NOPERM: i32 = 77;
+
+#[derive(Args, Debug)]
+pub struct SendmailArgs {
+    /// Take recipients from the To, Cc and Bcc headers (plus any given as arguments)
+    #[arg(short = 't')]
+    read_headers: bool,
+    /// Accepted for compatibility; a lone "." never ends the message, stdin is read to EOF
+    #[arg(short = 'i')]
+    _ignore_dots: bool,
+    /// Envelope sender; used as From when the message has none
+    #[arg(short = 'f', value_name = "ADDR")]
+    sender: Option<String>,
+    /// Full name for the From header added for -f
+    #[arg(short = 'F', value_name = "NAME")]
+    full_name: Option<String>,
+    /// Sendmail options such as -oi, -odi or -oem; accepted and ignored
+    #[arg(short = 'o', value_name = "OPTION")]
+    _options: Vec<String>,
+    /// Body type (-B8BITMIME); accepted and ignored
+    #[arg(short = 'B', value_name = "TYPE", hide = true)]
+    _body_type: Option<String>,
+    /// Verbose; accepted and ignored
+    #[arg(short = 'v', hide = true)]
+    _verbose: bool,
+    /// Recipients; Gmail also delivers to the message's own To/Cc/Bcc
+    recipients: Vec<String>,
+}
+
+struct Failure(i32, String);
+
+impl<E: Into<Box<dyn Error + Send + Sync>>> From<E> for Failure {
+    fn from(e: E) -> Self {
+        let e = e.into();
+        Failure(exit_code(e.as_ref()), e.to_string())
+    }
+}
+
+// map a send failure to the status sendmail callers expect: retry later, give up, or fix the input
+fn exit_code(e: &(dyn Error + Send + Sync + 'static)) -> i32 {
+    if let Some(api) = e.downcast_ref::<ApiError>() {
+        return match api.status {
+            400 => EX_DATAERR,
+            401 | 403 => EX_NOPERM,
+            408 | 429 | 500..=599 => EX_TEMPFAIL,
+            _ => EX_UNAVAILABLE,
+        };
+    }
+    if e.downcast_ref::<reqwest::Error>().is_some() {
+        return EX_TEMPFAIL;
+    }
+    if e.downcast_ref::<std::io::Error>().is_some() {
+        return EX_IOERR;
+    }
+    EX_SOFTWARE
+}
+
+/// Send the message on stdin and return a sysexits status.
+pub fn run(args: SendmailArgs, offline: bool) -> i32 {
+    match send(args, offline) {
+        Ok(()) => EX_OK,
+        Err(Failure(code, msg)) => {
+            log::error!("sendmail failed (exit {}): {}", code, msg);
+            eprintln!("sendmail: {}", msg);
+            code
+        }
+    }
+}
+
+fn send(args: SendmailArgs, offline: bool) -> Result<(), Failure> {
+    let mut input = Vec::new();
+    std::io::stdin().read_to_end(&mut input)?;
+    let (msg, unlisted) = prepare(&input, &args)?;
+    if !unlisted.is_empty() {
+        // Gmail delivers to every header recipient; sendmail without -t would not
+        let warning = format!("header recipients not given as arguments also get the message: {}", unlisted.join(", "));
+        log::warn!("sendmail: {}", warning);
+        eprintln!("sendmail: warning: {}", warning);
+    }
+
+    if offline {
+        return Err(Failure(EX_TEMPFAIL, "not sending while --offline".into()));
+    }
+    let (id, secret) = crate::config::get().credentials().unwrap_or_default();
+    let token = crate::auth::mail::access_token(&id, &secret).map_err(|e| {
+        // with no saved login retrying won't help; a failed refresh may be transient
+        let code = if crate::token_store::load_token().is_ok() { EX_TEMPFAIL } else { EX_NOPERM };
+        Failure(code, e.to_string())
+    })?;
+    crate::gmail::send_mail(&token, &msg.into(), None)?;
+    Ok(())
+}
+
+struct Field {
+    name: String,
+    /// unfolded value
+    value: String,
+    /// the field as it appeared, continuation lines included
+    raw: String,
+}
+
+// "Subject", "X-Mailer", ...: printable ASCII without spaces (RFC 5322 §2.2)
+fn is_field_name(name: &str) -> bool {
+    !name.is_empty() && name.bytes().all(|b| b.is_ascii_graphic())
+}
+
+/// The header fields and where the body starts. Input that doesn't begin with a
+/// header field is all body; a header block with a broken line in it is an error.
+fn parse_headers(input: &[u8]) -> Result<(Vec<Field>, usize), String> {
+    let (head_len, body_start) = match find(input, b"\r\n\r\n") {
+        Some(i) => (i, i + 4),
+        None => match find(input, b"\n\n") {
+            Some(i) => (i, i + 2),
+            None => (input.len(), input.len()),
+        },
+    };
+    let head = String::from_utf8_lossy(&input[..head_len]);
+    let mut fields: Vec<Field> = Vec::new();
+    for line in head.split_inclusive('\n') {
+        let text = line.trim_end_matches(['\r', '\n']);
+        if text.starts_with([' ', '\t']) && !fields.is_empty() {
+            let f = fields.last_mut().unwrap();
+            f.value.push(' ');
+            f.value.push_str(text.trim());
+            f.raw.push_str(line);
+        } else if let Some((name, value)) = text.split_once(':').filter(|(n, _)| is_field_name(n)) {
+            fields.push(Field { name: name.to_string(), value: value.trim().to_string(), raw: line.to_string() });
+        } else if fields.is_empty() {
+            return Ok((Vec::new(), 0));
+        } else {
+            return Err(format!("malformed header line {:?}", text));
+        }
+    }
+    Ok((fields, body_start))
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+/// Work out the recipients and rewrite the header block so Gmail delivers to all of them:
+/// the API only honors header recipients, so argument recipients missing from To/Cc/Bcc
+/// become Bcc. Adds a From for -f when the message has none. Also returns, without -t, the
+/// header recipients missing from the arguments.
+fn prepare(input: &[u8], args: &SendmailArgs) -> Result<(Vec<u8>, Vec<String>), Failure> {
+    if input.iter().all(u8::is_ascii_whitespace) {
+        return Err(Failure(EX_DATAERR, "empty message on stdin".into()));
+    }
+    let (fields, body_start) = parse_headers(input).map_err(|e| Failure(EX_DATAERR, e))?;
+    let header_values = |names: &[&str]| -> Vec<String> {
+        fields
+            .iter()
+            .filter(|f| names.iter().any(|want| f.name.eq_ignore_ascii_case(want)))
+            .flat_map(|f| split_addresses(&f.value))
+            .collect()
+    };
+    let in_headers = header_values(&["To", "Cc", "Bcc"]);
+    let from_args: Vec<String> = args.recipients.iter().flat_map(|r| split_addresses(r)).collect();
+    if from_args.is_empty() && (!args.read_headers || in_headers.is_empty()) {
+        return Err(Failure(EX_USAGE, "no recipients given (pass addresses or use -t)".into()));
+    }
+
+    let known: Vec<String> = in_headers.iter().map(|a| addr_spec(a).to_lowercase()).collect();
+    let unlisted: Vec<String> = if args.read_headers {
+        Vec::new()
+    } else {
+        let listed: Vec<String> = from_args.iter().map(|a| addr_spec(a).to_lowercase()).collect();
+        in_headers.iter().filter(|a| !listed.contains(&addr_spec(a).to_lowercase())).cloned().collect()
+    };
+    let mut bcc = header_values(&["Bcc"]);
+    for r in &from_args {
+        let spec = addr_spec(r).to_lowercase();
+        if !known.contains(&spec) && !bcc.iter().any(|b| addr_spec(b).eq_ignore_ascii_case(&spec)) {
+            bcc.push(r.clone());
+        }
+    }
+
+    let nl = if find(input, b"\r\n").is_some() { "\r\n" } else { "\n" };
+    let mut head = String::new();
+    if !fields.iter().any(|f| f.name.eq_ignore_ascii_case("From"))
+        && let Some(sender) = &args.sender
+    {
+        match &args.full_name {
+            Some(name) => head.push_str(&format!("From: \"{}\" <{}>{}", name.replace('"', ""), sender, nl)),
+            None => head.push_str(&format!("From: {}{}", sender, nl)),
+        }
+    }
+    for f in fields.iter().filter(|f| !f.name.eq_ignore_ascii_case("Bcc")) {
+        head.push_str(&f.raw);
+        if !f.raw.ends_with('\n') {
+            head.push_str(nl);
+        }
+    }
+    if !bcc.is_empty() {
+        head.push_str(&format!("Bcc: {}{}", bcc.join(", "), nl));
+    }
+    head.push_str(nl);
+
+    let mut out = head.into_bytes();
+    out.extend_from_slice(&input[body_start..]);
+    Ok((out, unlisted))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(recipients: &[&str]) -> SendmailArgs {
+        SendmailArgs {
+            read_headers: false,
+            _ignore_dots: false,
+            sender: None,
+            full_name: None,
+            _options: Vec::new(),
+            _body_type: None,
+            _verbose: false,
+            recipients: recipients.iter().map(|r| r.to_string()).collect(),
+        }
+    }
+
+    fn prepared(input: &str, args: &SendmailArgs) -> String {
+        String::from_utf8(prepare(input.as_bytes(), args).ok().unwrap().0).unwrap()
+    }
+
+    #[test]
+    fn headers_end_at_the_blank_line() {
+        let input = b"To: a@example.com\nSubject: hi\n\nbody\n";
+        let (fields, start) = parse_headers(input).unwrap();
+        assert_eq!(fields.len(), 2);
+        assert_eq!(fields[1].value, "hi");
+        assert_eq!(&input[start..], b"body\n");
+
+        let input = b"To: a@example.com\r\nSubject: hi\r\n\r\nbody\r\n";
+        let (fields, start) = parse_headers(input).unwrap();
+        assert_eq!(fields[0].value, "a@example.com");
+        assert_eq!(fields[0].raw, "To: a@example.com\r\n");
+        assert_eq!(&input[start..], b"body\r\n");
+    }
+
+    #[test]
+    fn folded_headers_are_unfolded() {
+        let (fields, _) = parse_headers(b"Subject: a long\n\tsubject line\nTo: a@example.com\n\nbody").unwrap();
+        assert_eq!(fields[0].value, "a long subject line");
+        assert_eq!(fields[0].raw, "Subject: a long\n\tsubject line\n");
+    }
+
+    #[test]
+    fn input_without_headers_is_all_body() {
+        let (fields, start) = parse_headers(b"just a note\n\nmore").unwrap();
+        assert!(fields.is_empty());
+        assert_eq!(start, 0);
+    }
+
+    #[test]
+    fn a_broken_header_line_is_an_error() {
+        assert!(parse_headers(b"To: a@example.com\nnot a header\n\nbody").is_err());
+        let failure = prepare(b"Subject: hi\nbad line\n\nbody", &args(&["a@example.com"])).err().unwrap();
+        assert_eq!(failure.0, EX_DATAERR);
+    }
+
+    #[test]
+    fn argument_recipients_become_bcc() {
+        let out = prepared("To: a@example.com\nBcc: b@example.com\n\nbody", &args(&["a@example.com", "c@example.com"]));
+        assert_eq!(out, "To: a@example.com\nBcc: b@example.com, c@example.com\n\nbody");
+    }
+
+    #[test]
+    fn crlf_input_keeps_crlf() {
+        let out = prepared("To: a@example.com\r\n\r\nbody\r\n", &args(&["c@example.com"]));
+        assert_eq!(out, "To: a@example.com\r\nBcc: c@example.com\r\n\r\nbody\r\n");
+    }
+
+    #[test]
+    fn sender_flags_add_a_from() {
+        let mut a = args(&["a@example.com"]);
+        a.sender = Some("cron@example.com".into());
+        assert!(prepared("To: a@example.com\n\nbody", &a).starts_with("From: cron@example.com\n"));
+        a.full_name = Some("Cron \"Daemon\"".into());
+        assert!(prepared("To: a@example.com\n\nbody", &a).starts_with("From: \"Cron Daemon\" <cron@example.com>\n"));
+        // a From in the message wins
+        assert!(prepared("From: me@example.com\nTo: a@example.com\n\nbody", &a).starts_with("From: me@example.com\n"));
+    }
+
+    #[test]
+    fn header_recipients_missing_from_the_arguments_are_reported() {
+        let input = b"To: a@example.com, Other <o@example.com>\n\nbody";
+        let (_, unlisted) = prepare(input, &args(&["a@example.com"])).ok().unwrap();
+        assert_eq!(unlisted, vec!["Other <o@example.com>".to_string()]);
+        let mut with_t = args(&[]);
+        with_t.read_headers = true;
+        assert!(prepare(input, &with_t).ok().unwrap().1.is_empty());
+    }
+}
@@ -0,0 +1,211 @@
+use rusqlite::{Connection, Row, params};
+use std::path::PathBuf;
+
+use super::account_dir;
+use crate::gmail::SimpleMail;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS messages (
+    id            TEXT PRIMARY KEY,
+    thread_id     TEXT,
+    subject       TEXT,
+    from_addr     TEXT,
+    to_addr       TEXT,
+    cc            TEXT,
+    reply_to      TEXT,
+    date          TEXT,
+    message_id    TEXT,
+    in_reply_to   TEXT,
+    refs          TEXT,
+    snippet       TEXT,
+    body          TEXT,
+    internal_date INTEGER,
+    in_inbox      INTEGER NOT NULL DEFAULT 0
+);
+CREATE INDEX IF NOT EXISTS messages_inbox_date ON messages (in_inbox, internal_date DESC);
+CREATE INDEX IF NOT EXISTS messages_thread ON messages (thread_id);
+";
+
+// applied in order on top of SCHEMA; PRAGMA user_version records how many have run
+const MIGRATIONS: &[&str] = &["
+ALTER TABLE messages ADD COLUMN has_attachment INTEGER NOT NULL DEFAULT 0;
+CREATE VIRTUAL TABLE messages_fts USING fts5(
+    subject, from_addr, to_addr, cc, snippet, body,
+    content = 'messages', content_rowid = 'rowid', tokenize = 'unicode61 remove_diacritics 2'
+);
+CREATE TRIGGER messages_fts_insert AFTER INSERT ON messages BEGIN
+    INSERT INTO messages_fts (rowid, subject, from_addr, to_addr, cc, snippet, body)
+    VALUES (new.rowid, new.subject, new.from_addr, new.to_addr, new.cc, new.snippet, new.body);
+END;
+CREATE TRIGGER messages_fts_delete AFTER DELETE ON messages BEGIN
+    INSERT INTO messages_fts (messages_fts, rowid, subject, from_addr, to_addr, cc, snippet, body)
+    VALUES ('delete', old.rowid, old.subject, old.from_addr, old.to_addr, old.cc, old.snippet, old.body);
+END;
+CREATE TRIGGER messages_fts_update AFTER UPDATE ON messages BEGIN
+    INSERT INTO messages_fts (messages_fts, rowid, subject, from_addr, to_addr, cc, snippet, body)
+    VALUES ('delete', old.rowid, old.subject, old.from_addr, old.to_addr, old.cc, old.snippet, old.body);
+    INSERT INTO messages_fts (rowid, subject, from_addr, to_addr, cc, snippet, body)
+    VALUES (new.rowid, new.subject, new.from_addr, new.to_addr, new.cc, new.snippet, new.body);
+END;
+INSERT INTO messages_fts (messages_fts) VALUES ('rebuild');
+", "
+ALTER TABLE messages ADD COLUMN unread INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE messages ADD COLUMN starred INTEGER NOT NULL DEFAULT 0;
+", "
+ALTER TABLE messages ADD COLUMN size INTEGER;
+ALTER TABLE messages ADD COLUMN labels TEXT;
+"];
+
+/// How many cached inbox messages the list shows.
+pub const INBOX_LIMIT: usize = 500;
+
+pub(super) const COLUMNS: &str = "id, thread_id, subject, from_addr, to_addr, cc, reply_to, date, message_id, in_reply_to, refs, snippet, body, internal_date, has_attachment, unread, starred, size, labels";
+
+fn cache_file() -> PathBuf {
+    let mut d = account_dir();
+    d.push("cache.sqlite");
+    d
+}
+
+/// Open (and if needed create) the local message cache.
+pub fn open() -> rusqlite::Result<Connection> {
+    let _ = std::fs::create_dir_all(account_dir());
+    let mut conn = Connection::open(cache_file())?;
+    // the fetch thread writes while the UI reads
+    conn.pragma_update(None, "journal_mode", "WAL")?;
+    conn.busy_timeout(std::time::Duration::from_secs(5))?;
+    conn.execute_batch(SCHEMA)?;
+    migrate(&mut conn)?;
+    Ok(conn)
+}
+
+fn migrate(conn: &mut Connection) -> rusqlite::Result<()> {
+    let version: usize = conn.pragma_query_value(None, "user_version", |r| r.get(0))?;
+    for (i, sql) in MIGRATIONS.iter().enumerate().skip(version) {
+        let tx = conn.transaction()?;
+        tx.execute_batch(sql)?;
+        tx.pragma_update(None, "user_version", i + 1)?;
+        tx.commit()?;
+    }
+    Ok(())
+}
+
+pub(super) fn from_row(r: &Row) -> rusqlite::Result<SimpleMail> {
+    Ok(SimpleMail {
+        id: r.get(0)?,
+        thread_id: r.get(1)?,
+        subject: r.get(2)?,
+        from: r.get(3)?,
+        to: r.get(4)?,
+        cc: r.get(5)?,
+        reply_to: r.get(6)?,
+        date: r.get(7)?,
+        message_id: r.get(8)?,
+        in_reply_to: r.get(9)?,
+        references: r.get(10)?,
+        snippet: r.get(11)?,
+        body: r.get(12)?,
+        internal_date: r.get(13)?,
+        has_attachment: r.get(14)?,
+        unread: r.get(15)?,
+        starred: r.get(16)?,
+        size: r.get(17)?,
+        // a JSON array of names
+        labels: r.get::<_, Option<String>>(18)?.and_then(|l| serde_json::from_str(&l).ok()).unwrap_or_default(),
+    })
+}
+
+/// Insert or refresh messages. `inbox` marks them as part of the inbox listing;
+/// messages seen only through search or threads keep their existing flag.
+pub fn store_messages(msgs: &[SimpleMail], inbox: bool) -> rusqlite::Result<()> {
+    let mut conn = open()?;
+    let tx = conn.transaction()?;
+    {
+        let mut stmt = tx.prepare(&format!(
+            "INSERT INTO messages ({}, in_inbox) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20)
+             ON CONFLICT(id) DO UPDATE SET
+                thread_id = excluded.thread_id, subject = excluded.subject, from_addr = excluded.from_addr,
+                to_addr = excluded.to_addr, cc = excluded.cc, reply_to = excluded.reply_to, date = excluded.date,
+                message_id = excluded.message_id, in_reply_to = excluded.in_reply_to, refs = excluded.refs,
+                snippet = excluded.snippet, body = COALESCE(excluded.body, messages.body),
+                internal_date = excluded.internal_date,
+                has_attachment = excluded.has_attachment, unread = excluded.unread, starred = excluded.starred,
+                size = excluded.size, labels = excluded.labels,
+                in_inbox = MAX(messages.in_inbox, excluded.in_inbox)",
+            COLUMNS
+        ))?;
+        for m in msgs {
+            stmt.execute(params![
+                m.id,
+                m.thread_id,
+                m.subject,
+                m.from,
+                m.to,
+                m.cc,
+                m.reply_to,
+                m.date,
+                m.message_id,
+                m.in_reply_to,
+                m.references,
+                m.snippet,
+                m.body,
+                m.internal_date,
+                m.has_attachment,
+                m.unread,
+                m.starred,
+                m.size,
+                serde_json::to_string(&m.labels).ok(),
+                inbox as i64,
+            ])?;
+        }
+    }
+    tx.commit()
+}
+
+/// Store the result of an inbox fetch (the newest messages in the inbox) and un-flag
+/// cached messages in the same time range that are no longer there, e.g. archived ones.
+pub fn sync_inbox(latest: &[SimpleMail]) -> rusqlite::Result<()> {
+    store_messages(latest, true)?;
+    let Some(oldest) = latest.iter().filter_map(|m| m.internal_date).min() else {
+        return Ok(());
+    };
+    let conn = open()?;
+    let ids = serde_json::to_string(&latest.iter().map(|m| m.id.as_str()).collect::<Vec<_>>())
+        .unwrap_or_else(|_| "[]".into());
+    conn.execute(
+        "UPDATE messages SET in_inbox = 0
+         WHERE in_inbox = 1 AND internal_date >= ?1 AND id NOT IN (SELECT value FROM json_each(?2))",
+        params![oldest, ids],
+    )?;
+    Ok(())
+}
+
+/// Cached inbox messages, newest first.
+pub fn load_inbox(limit: usize) -> rusqlite::Result<Vec<SimpleMail>> {
+    let conn = open()?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM messages WHERE in_inbox = 1 ORDER BY internal_date DESC LIMIT ?1",
+        COLUMNS
+    ))?;
+    let rows = stmt.query_map(params![limit as i64], from_row)?;
+    rows.collect()
+}
+
+/// Cached messages of one conversation, oldest first.
+pub fn load_thread(thread_id: &str) -> rusqlite::Result<Vec<SimpleMail>> {
+    let conn = open()?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM messages WHERE thread_id = ?1 ORDER BY internal_date ASC",
+        COLUMNS
+    ))?;
+    let rows = stmt.query_map(params![thread_id], from_row)?;
+    rows.collect()
+}
+
+/// One cached message by Gmail id.
+pub fn load_message(id: &str) -> rusqlite::Result<Option<SimpleMail>> {
+    let conn = open()?;
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM messages WHERE id = ?1", COLUMNS))?;
+    let mut rows = stmt.query_map(params![id], from_row)?;
+    rows.next().transpose()
+}
@@ -0,0 +1,382 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use super::config_dir;
+use crate::compose::{addr_spec, split_addresses};
+use crate::gmail::SimpleMail;
+
+// message ids already counted, so the periodic refetch doesn't inflate usage
+const MAX_SEEN_IDS: usize = 5000;
+// sending to someone says more about relevance than receiving from them
+const SENT_WEIGHT: u32 = 3;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Contact {
+    pub email: String,
+    pub name: Option<String>,
+    /// mutt alias / nickname, also matched by autocomplete
+    pub alias: Option<String>,
+    pub count: u32,
+    pub last_used_unix: i64,
+}
+
+impl Contact {
+    /// Recipient form, e.g. "Alice Example <alice@example.com>".
+    pub fn address(&self) -> String {
+        match self.name.as_deref().filter(|n| !n.is_empty()) {
+            Some(name) if name.contains([',', ';', '"']) => format!("\"{}\" <{}>", name.replace('"', ""), self.email),
+            Some(name) => format!("{} <{}>", name, self.email),
+            None => self.email.clone(),
+        }
+    }
+
+    // usage frequency decayed by recency (30-day half-life-ish)
+    fn frecency(&self, now: i64) -> f64 {
+        let age_days = (now - self.last_used_unix).max(0) as f64 / 86_400.0;
+        self.count as f64 / (1.0 + age_days / 30.0)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct ContactBook {
+    pub contacts: Vec<Contact>,
+    #[serde(default)]
+    seen_ids: Vec<String>,
+}
+
+// the fetch thread and the UI both update the book; serialize load-modify-save
+static FILE_LOCK: Mutex<()> = Mutex::new(());
+
+/// Load the book, apply `f` and save it if `f` reports a change.
+pub fn update(f: impl FnOnce(&mut ContactBook) -> bool) -> io::Result<()> {
+    let _guard = FILE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut book = ContactBook::load();
+    if f(&mut book) {
+        book.save()?;
+    }
+    Ok(())
+}
+
+fn contacts_file() -> PathBuf {
+    let mut d = config_dir();
+    d.push("contacts.json");
+    d
+}
+
+fn now_unix() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+// "Alice <a@x>" -> Some("Alice"); bare addresses have no name
+fn display_name(addr: &str) -> Option<String> {
+    let i = addr.find('<')?;
+    let name = addr[..i].trim().trim_matches('"').trim();
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+// how well `query` matches `text`: substring beats subsequence, earlier beats later
+fn match_score(text: &str, query: &str) -> Option<f64> {
+    if let Some(pos) = text.find(query) {
+        let word_start = pos == 0 || !text.as_bytes()[pos - 1].is_ascii_alphanumeric();
+        return Some(if word_start { 100.0 } else { 50.0 } - pos.min(40) as f64);
+    }
+    let mut chars = text.chars();
+    query.chars().all(|q| chars.any(|c| c == q)).then_some(5.0)
+}
+
+impl ContactBook {
+    pub fn load() -> Self {
+        fs::read_to_string(contacts_file())
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        fs::create_dir_all(config_dir())?;
+        let tmp = contacts_file().with_extension("tmp");
+        let data = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        let mut f = fs::File::create(&tmp)?;
+        f.write_all(data.as_bytes())?;
+        f.flush()?;
+        fs::rename(tmp, contacts_file())?;
+        Ok(())
+    }
+
+    fn upsert(&mut self, addr: &str, weight: u32, when: i64) {
+        let email = addr_spec(addr).to_lowercase();
+        if !email.contains('@') {
+            return;
+        }
+        let name = display_name(addr);
+        match self.contacts.iter_mut().find(|c| c.email == email) {
+            Some(c) => {
+                c.count += weight;
+                c.last_used_unix = c.last_used_unix.max(when);
+                if c.name.is_none() {
+                    c.name = name;
+                }
+            }
+            None => self.contacts.push(Contact { email, name, alias: None, count: weight, last_used_unix: when }),
+        }
+    }
+
+    /// Count the From/To/Cc addresses of messages not seen before. Returns true if anything changed.
+    pub fn record_messages(&mut self, msgs: &[SimpleMail]) -> bool {
+        let mut changed = false;
+        for m in msgs {
+            if self.seen_ids.contains(&m.id) {
+                continue;
+            }
+            let when = m.internal_date.map(|ms| ms / 1000).unwrap_or_else(now_unix);
+            for header in [&m.from, &m.to, &m.cc].into_iter().flatten() {
+                for addr in split_addresses(header) {
+                    self.upsert(&addr, 1, when);
+                }
+            }
+            self.seen_ids.push(m.id.clone());
+            changed = true;
+        }
+        if self.seen_ids.len() > MAX_SEEN_IDS {
+            let excess = self.seen_ids.len() - MAX_SEEN_IDS;
+            self.seen_ids.drain(..excess);
+        }
+        changed
+    }
+
+    /// Count the recipients of a message the user sent.
+    pub fn record_sent(&mut self, recipient_lists: &[&str]) {
+        let now = now_unix();
+        for list in recipient_lists {
+            for addr in split_addresses(list) {
+                self.upsert(&addr, SENT_WEIGHT, now);
+            }
+        }
+    }
+
+    /// Add "Name <email>" (or a bare address), or with `old` change that contact's name and
+    /// address, keeping its usage counts and alias. Returns the address as stored.
+    pub fn set(&mut self, old: Option<&str>, addr: &str) -> Result<String, String> {
+        let email = addr_spec(addr).trim().to_lowercase();
+        if !email.contains('@') || email.contains(char::is_whitespace) {
+            return Err(format!("not an email address: {:?}", addr));
+        }
+        let name = display_name(addr);
+        let taken = self.contacts.iter().any(|c| c.email == email && old.is_none_or(|o| !c.email.eq_ignore_ascii_case(o)));
+        if taken {
+            return Err(format!("{} is already in the address book", email));
+        }
+        let existing = old.and_then(|o| self.contacts.iter_mut().find(|c| c.email.eq_ignore_ascii_case(o)));
+        let contact = match existing {
+            Some(c) => {
+                c.email = email;
+                c.name = name;
+                c.clone()
+            }
+            None => {
+                let c = Contact { email, name, alias: None, count: 0, last_used_unix: now_unix() };
+                self.contacts.push(c.clone());
+                c
+            }
+        };
+        Ok(contact.address())
+    }
+
+    pub fn remove(&mut self, email: &str) {
+        self.contacts.retain(|c| !c.email.eq_ignore_ascii_case(email));
+    }
+
+    /// Best matches for a partially typed recipient, ranked by match quality and frecency.
+    pub fn suggest(&self, query: &str, limit: usize) -> Vec<&Contact> {
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return Vec::new();
+        }
+        let now = now_unix();
+        let mut scored: Vec<(f64, &Contact)> = self
+            .contacts
+            .iter()
+            .filter_map(|c| {
+                let fields = [Some(c.email.as_str()), c.name.as_deref(), c.alias.as_deref()];
+                let best = fields
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|f| match_score(&f.to_lowercase(), &query))
+                    .fold(None, |acc: Option<f64>, s| Some(acc.map_or(s, |a| a.max(s))))?;
+                Some((best * (1.0 + (1.0 + c.frecency(now)).ln()), c))
+            })
+            .collect();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        scored.into_iter().take(limit).map(|(_, c)| c).collect()
+    }
+
+    /// Import a vCard (.vcf) or mutt alias file, chosen by extension. Returns the number of addresses added or updated.
+    pub fn import(&mut self, path: &Path) -> io::Result<usize> {
+        let content = fs::read_to_string(path)?;
+        let is_vcard = path
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("vcf") || e.eq_ignore_ascii_case("vcard"))
+            || content.trim_start().to_uppercase().starts_with("BEGIN:VCARD");
+        let entries = if is_vcard { parse_vcards(&content) } else { parse_mutt_aliases(&content) };
+
+        let now = now_unix();
+        for (alias, addr) in &entries {
+            self.upsert(addr, 0, now);
+            let email = addr_spec(addr).to_lowercase();
+            if let Some(c) = self.contacts.iter_mut().find(|c| c.email == email) {
+                // imported names are deliberate; prefer them over harvested ones
+                if let Some(name) = display_name(addr) {
+                    c.name = Some(name);
+                }
+                if alias.is_some() {
+                    c.alias = alias.clone();
+                }
+            }
+        }
+        Ok(entries.len())
+    }
+}
+
+/// (alias, "Name <email>") pairs from vCard 3/4 data.
+fn parse_vcards(content: &str) -> Vec<(Option<String>, String)> {
+    // unfold continuation lines (RFC 6350 §3.2)
+    let mut lines: Vec<String> = Vec::new();
+    for raw in content.lines() {
+        match raw.strip_prefix([' ', '\t']) {
+            Some(cont) if !lines.is_empty() => lines.last_mut().unwrap().push_str(cont),
+            _ => lines.push(raw.trim_end().to_string()),
+        }
+    }
+
+    let mut out = Vec::new();
+    let mut name: Option<String> = None;
+    let mut nickname: Option<String> = None;
+    let mut emails: Vec<String> = Vec::new();
+    for line in lines {
+        let Some((key, value)) = line.split_once(':') else { continue };
+        // property name without parameters or group prefix, e.g. "item1.EMAIL;TYPE=work"
+        let prop = key.split(';').next().unwrap_or("").rsplit('.').next().unwrap_or("").to_uppercase();
+        let value = value.replace("\\,", ",").replace("\\;", ";").replace("\\\\", "\\");
+        match prop.as_str() {
+            "BEGIN" => {
+                name = None;
+                nickname = None;
+                emails.clear();
+            }
+            "FN" => name = Some(value.trim().to_string()).filter(|n| !n.is_empty()),
+            "NICKNAME" => nickname = value.split(',').next().map(|n| n.trim().to_string()).filter(|n| !n.is_empty()),
+            "EMAIL" => emails.push(value.trim().to_string()),
+            "END" => {
+                for e in emails.drain(..) {
+                    let addr = match &name {
+                        Some(n) => format!("{} <{}>", n, e),
+                        None => e,
+                    };
+                    out.push((nickname.clone(), addr));
+                }
+            }
+            _ => {}
+        }
+    }
+    out
+}
+
+/// (alias, address) pairs from mutt `alias nick Name <email>, other@x` lines.
+fn parse_mutt_aliases(content: &str) -> Vec<(Option<String>, String)> {
+    let mut out = Vec::new();
+    for line in content.lines() {
+        let Some(mut rest) = line.trim().strip_prefix("alias ") else { continue };
+        // optional "-group name" pairs precede the nickname
+        while let Some(r) = rest.trim_start().strip_prefix("-group") {
+            rest = r.trim_start().split_once(char::is_whitespace).map(|(_, t)| t).unwrap_or("");
+        }
+        let Some((nick, addrs)) = rest.trim_start().split_once(char::is_whitespace) else { continue };
+        let addrs = addrs.split('#').next().unwrap_or("");
+        for addr in split_addresses(addrs) {
+            out.push((Some(nick.to_string()), addr));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contact(email: &str, name: Option<&str>, count: u32, days_ago: i64) -> Contact {
+        Contact {
+            email: email.into(),
+            name: name.map(str::to_string),
+            alias: None,
+            count,
+            last_used_unix: now_unix() - days_ago * 86_400,
+        }
+    }
+
+    #[test]
+    fn vcards_unfold_lines_and_keep_every_email() {
+        let vcf = "BEGIN:VCARD\r\nVERSION:3.0\r\nFN:Alice Ex\r\n ample\r\nNICKNAME:ali,al\r\nitem1.EMAIL;TYPE=work:alice@work.exa\r\n mple\r\nEMAIL;TYPE=home:alice@home.example\r\nEND:VCARD\r\nBEGIN:VCARD\r\nFN:Smith\\, Bob\r\nEMAIL:bob@example.com\r\nEND:VCARD\r\n";
+        assert_eq!(
+            parse_vcards(vcf),
+            vec![
+                (Some("ali".to_string()), "Alice Example <alice@work.example>".to_string()),
+                (Some("ali".to_string()), "Alice Example <alice@home.example>".to_string()),
+                (None, "Smith, Bob <bob@example.com>".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn mutt_aliases_with_quoted_names_and_groups() {
+        let aliases = "alias bob \"Smith, Bob\" <bob@example.com>\n# a comment\nalias -group work team ann@example.com, \"Doe, Jo\" <jo@example.com> # the team\n";
+        let entries = parse_mutt_aliases(aliases);
+        let team = Some("team".to_string());
+        assert_eq!(
+            entries,
+            vec![
+                (Some("bob".to_string()), "\"Smith, Bob\" <bob@example.com>".to_string()),
+                (team.clone(), "ann@example.com".to_string()),
+                (team, "\"Doe, Jo\" <jo@example.com>".to_string()),
+            ]
+        );
+        assert_eq!(display_name(&entries[0].1).as_deref(), Some("Smith, Bob"));
+    }
+
+    #[test]
+    fn suggestions_rank_by_match_then_frecency() {
+        let book = ContactBook {
+            contacts: vec![
+                contact("old@example.com", Some("Ann Old"), 20, 365),
+                contact("joanne@example.com", None, 3, 0),
+                contact("ann@example.com", Some("Ann Recent"), 20, 1),
+                contact("anna@example.com", None, 1, 1),
+            ],
+            seen_ids: Vec::new(),
+        };
+        let emails: Vec<&str> = book.suggest("ann", 10).iter().map(|c| c.email.as_str()).collect();
+        // frequently and recently used first among word-start matches; a match inside a word
+        // ranks below them
+        assert_eq!(emails, ["ann@example.com", "old@example.com", "anna@example.com", "joanne@example.com"]);
+        assert_eq!(book.suggest("ann", 1).len(), 1);
+        assert!(book.suggest("  ", 10).is_empty());
+    }
+
+    #[test]
+    fn set_adds_and_edits_contacts() {
+        let mut book = ContactBook::default();
+        assert_eq!(book.set(None, "Ann <Ann@Example.com>"), Ok("Ann <ann@example.com>".to_string()));
+        book.contacts[0].count = 7;
+        assert_eq!(book.set(Some("ann@example.com"), "Ann B <ann.b@example.com>"), Ok("Ann B <ann.b@example.com>".to_string()));
+        assert_eq!(book.contacts.len(), 1);
+        assert_eq!(book.contacts[0].count, 7);
+        book.set(None, "bob@example.com").unwrap();
+        assert!(book.set(Some("bob@example.com"), "ann.b@example.com").is_err());
+        assert!(book.set(None, "not an address").is_err());
+    }
+}
@@ -0,0 +1,206 @@
+use chrono::{Local, NaiveDate, TimeZone};
+use rusqlite::params_from_iter;
+use rusqlite::types::Value;
+
+use super::cache::{self, COLUMNS};
+use crate::gmail::SimpleMail;
+
+// bm25 weights per indexed column: subject, from_addr, to_addr, cc, snippet, body
+const RANK: &str = "bm25(messages_fts, 10.0, 5.0, 3.0, 2.0, 1.0, 1.0)";
+
+/// A search query split into full-text terms and plain column filters.
+#[derive(Debug, Default)]
+struct Query {
+    /// FTS5 expressions, ANDed together
+    terms: Vec<String>,
+    has_attachment: bool,
+    /// receive-time bounds in ms since the epoch: after is inclusive, before exclusive
+    after: Option<i64>,
+    before: Option<i64>,
+}
+
+// `"a b" from:x` -> ["a b", "from:x"]; quotes group words, also after a prefix (subject:"a b")
+fn tokenize(input: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut cur = String::new();
+    let mut quoted = false;
+    for c in input.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !cur.is_empty() {
+                    out.push(std::mem::take(&mut cur));
+                }
+            }
+            c => cur.push(c),
+        }
+    }
+    if !cur.is_empty() {
+        out.push(cur);
+    }
+    out
+}
+
+// a quoted FTS5 phrase, prefix-matched so partially typed words still hit; inside the quotes
+// operators and special characters (-, *, ^, AND, NEAR(...)) are plain text
+fn phrase(text: &str) -> Option<String> {
+    if !text.chars().any(char::is_alphanumeric) {
+        return None;
+    }
+    Some(format!("\"{}\"*", text.replace('"', "\"\"")))
+}
+
+// start of the given local day, accepting 2024/01/31 and 2024-01-31
+fn day_start_ms(date: &str) -> Option<i64> {
+    let d = NaiveDate::parse_from_str(date, "%Y/%m/%d")
+        .or_else(|_| NaiveDate::parse_from_str(date, "%Y-%m-%d"))
+        .ok()?;
+    let t = Local.from_local_datetime(&d.and_hms_opt(0, 0, 0)?).earliest()?;
+    Some(t.timestamp_millis())
+}
+
+fn parse(input: &str) -> Query {
+    let mut q = Query::default();
+    for token in tokenize(input) {
+        let (prefix, value) = token.split_once(':').unwrap_or(("", &token));
+        let columns = match prefix.to_lowercase().as_str() {
+            "from" => Some("from_addr"),
+            "to" => Some("{to_addr cc}"),
+            "cc" => Some("cc"),
+            "subject" => Some("subject"),
+            "has" if value.eq_ignore_ascii_case("attachment") => {
+                q.has_attachment = true;
+                continue;
+            }
+            "after" | "before" if day_start_ms(value).is_some() => {
+                let ms = day_start_ms(value);
+                if prefix.eq_ignore_ascii_case("after") {
+                    q.after = ms;
+                } else {
+                    q.before = ms;
+                }
+                continue;
+            }
+            _ => None,
+        };
+        let term = match columns {
+            Some(cols) => phrase(value).map(|p| format!("{} : {}", cols, p)),
+            // unknown prefixes (is:, label:, ...) are searched as plain text
+            None => phrase(&token),
+        };
+        q.terms.extend(term);
+    }
+    q
+}
+
+/// Search the local message cache. Supports free text plus `from:`, `to:`, `cc:`, `subject:`,
+/// `has:attachment`, `after:` and `before:` (YYYY/MM/DD); text matches are ranked by relevance,
+/// filter-only queries come back newest first.
+pub fn search(input: &str, limit: usize) -> rusqlite::Result<Vec<SimpleMail>> {
+    let q = parse(input);
+    let mut filters = Vec::new();
+    let mut args: Vec<Value> = Vec::new();
+    if !q.terms.is_empty() {
+        filters.push("messages_fts MATCH ?".to_string());
+        args.push(Value::Text(q.terms.join(" AND ")));
+    }
+    if q.has_attachment {
+        filters.push("m.has_attachment = 1".to_string());
+    }
+    if let Some(after) = q.after {
+        filters.push("m.internal_date >= ?".to_string());
+        args.push(Value::Integer(after));
+    }
+    if let Some(before) = q.before {
+        filters.push("m.internal_date < ?".to_string());
+        args.push(Value::Integer(before));
+    }
+    if filters.is_empty() {
+        return Ok(Vec::new());
+    }
+    args.push(Value::Integer(limit as i64));
+
+    let columns: Vec<String> = COLUMNS.split(", ").map(|c| format!("m.{}", c)).collect();
+    let sql = if q.terms.is_empty() {
+        format!(
+            "SELECT {} FROM messages m WHERE {} ORDER BY m.internal_date DESC LIMIT ?",
+            columns.join(", "),
+            filters.join(" AND ")
+        )
+    } else {
+        format!(
+            "SELECT {} FROM messages_fts JOIN messages m ON m.rowid = messages_fts.rowid
+             WHERE {} ORDER BY {}, m.internal_date DESC LIMIT ?",
+            columns.join(", "),
+            filters.join(" AND "),
+            RANK
+        )
+    };
+
+    let conn = cache::open()?;
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt.query_map(params_from_iter(args), cache::from_row)?;
+    rows.collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+
+    #[test]
+    fn prefixes_become_column_filters() {
+        let q = parse("from:alice to:bob subject:\"quarterly report\" budget");
+        assert_eq!(
+            q.terms,
+            [
+                "from_addr : \"alice\"*",
+                "{to_addr cc} : \"bob\"*",
+                "subject : \"quarterly report\"*",
+                "\"budget\"*"
+            ]
+        );
+        assert!(!q.has_attachment && q.after.is_none() && q.before.is_none());
+    }
+
+    #[test]
+    fn attachment_and_date_filters() {
+        let q = parse("has:attachment after:2024/01/31 before:2024-02-29 invoice");
+        assert!(q.has_attachment);
+        assert_eq!(q.after, day_start_ms("2024-01-31"));
+        assert_eq!(q.before, day_start_ms("2024/02/29"));
+        assert!(q.after < q.before);
+        assert_eq!(q.terms, ["\"invoice\"*"]);
+        // not a date, so searched as text
+        assert_eq!(parse("after:yesterday").terms, ["\"after:yesterday\"*"]);
+    }
+
+    #[test]
+    fn quotes_group_words() {
+        assert_eq!(tokenize("\"a b\"  c subject:\"d e\""), ["a b", "c", "subject:d e"]);
+        // an unbalanced quote runs to the end
+        assert_eq!(tokenize("x \"y z"), ["x", "y z"]);
+    }
+
+    #[test]
+    fn special_characters_never_break_the_match_expression() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE VIRTUAL TABLE messages_fts USING fts5(subject, from_addr, to_addr, cc, snippet, body);")
+            .unwrap();
+        conn.execute("INSERT INTO messages_fts (subject) VALUES ('re: -draft* \"final\" version')", []).unwrap();
+        for input in ["\"", "-", "* -", "-draft", "draft*", "a\"b", "NOT AND OR", "NEAR(a b)", "from:", "to:\"", "col:(x)", "^start", "½", "a+b", "🎉 x", "{subject}"] {
+            let terms = parse(input).terms;
+            if terms.is_empty() {
+                continue;
+            }
+            let expr = terms.join(" AND ");
+            let found: rusqlite::Result<i64> =
+                conn.query_row("SELECT count(*) FROM messages_fts WHERE messages_fts MATCH ?", [&expr], |r| r.get(0));
+            assert!(found.is_ok(), "{:?} -> {:?}: {:?}", input, expr, found);
+        }
+        let hits: i64 = conn
+            .query_row("SELECT count(*) FROM messages_fts WHERE messages_fts MATCH ?", [parse("-draft \"final\"").terms.join(" AND ")], |r| r.get(0))
+            .unwrap();
+        assert_eq!(hits, 1);
+    }
+}
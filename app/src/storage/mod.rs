@@ -1 +1,102 @@
+pub mod cache;
+pub mod index;
+pub mod contacts;
+pub mod outbox;
 pub mod token_store;
+pub mod search_history;
+
+use std::path::{Path, PathBuf};
+use std::sync::Once;
+
+// the cache and the outbox as kept before accounts were configured; the cache's WAL files
+// go with it or not at all
+const LEGACY_STATE: &[&[&str]] = &[&["cache.sqlite", "cache.sqlite-wal", "cache.sqlite-shm"], &["outbox"]];
+
+static MIGRATE: Once = Once::new();
+
+pub fn config_dir() -> PathBuf {
+    if let Some(dir) = dirs::config_dir() {
+        dir.join("mailtui")
+    } else {
+        let mut p = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+        p.push(".config/mailtui");
+        p
+    }
+}
+
+/// Logs and other files that aren't worth backing up: `$XDG_STATE_HOME/mailtui` where that
+/// exists, the local data dir otherwise.
+pub fn state_dir() -> PathBuf {
+    dirs::state_dir().or_else(dirs::data_local_dir).map(|d| d.join("mailtui")).unwrap_or_else(config_dir)
+}
+
+/// Where per-account state (token, message cache, outbox) lives: the config dir itself
+/// without configured accounts, `accounts/<name>` under it otherwise. The first call moves
+/// the state of an accounts-less setup over to the default account.
+pub fn account_dir() -> PathBuf {
+    let config = crate::config::get();
+    let dir = account_dir_of(config.account());
+    if config.is_default_account() {
+        MIGRATE.call_once(|| migrate_legacy(&config_dir(), &dir));
+    }
+    dir
+}
+
+// move the legacy state into `to` unless it already has its own; the token stays put,
+// `token_store` reads it from the old place
+fn migrate_legacy(from: &Path, to: &Path) {
+    for group in LEGACY_STATE {
+        if !from.join(group[0]).exists() || to.join(group[0]).exists() {
+            continue;
+        }
+        for name in group.iter().filter(|n| from.join(n).exists()) {
+            let (old, new) = (from.join(name), to.join(name));
+            let moved = std::fs::create_dir_all(to).and_then(|_| std::fs::rename(&old, &new));
+            match moved {
+                Ok(()) => log::info!("moved {} to {}", old.display(), new.display()),
+                Err(e) => log::warn!("failed to move {} to {}: {}", old.display(), new.display(), e),
+            }
+        }
+    }
+}
+
+pub fn account_dir_of(account: Option<&crate::config::Account>) -> PathBuf {
+    match account {
+        Some(a) => {
+            let name: String = a.name.chars().map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' }).collect();
+            config_dir().join("accounts").join(name)
+        }
+        None => config_dir(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy_state_moves_to_the_account() {
+        let root = std::env::temp_dir().join(format!("mailtui-migrate-{}", std::process::id()));
+        let account = root.join("accounts").join("work");
+        std::fs::create_dir_all(root.join("outbox")).unwrap();
+        std::fs::write(root.join("outbox").join("1.json"), "{}").unwrap();
+        std::fs::write(root.join("cache.sqlite"), "db").unwrap();
+        std::fs::write(root.join("cache.sqlite-wal"), "wal").unwrap();
+        std::fs::write(root.join("token.json"), "{}").unwrap();
+
+        migrate_legacy(&root, &account);
+        assert!(account.join("outbox").join("1.json").exists());
+        assert_eq!(std::fs::read_to_string(account.join("cache.sqlite-wal")).unwrap(), "wal");
+        assert!(!root.join("cache.sqlite").exists());
+        assert!(root.join("token.json").exists());
+
+        // an account that has its own cache keeps it, and the old WAL stays with the old db
+        std::fs::write(root.join("cache.sqlite"), "old").unwrap();
+        std::fs::write(root.join("cache.sqlite-wal"), "old wal").unwrap();
+        migrate_legacy(&root, &account);
+        assert_eq!(std::fs::read_to_string(account.join("cache.sqlite")).unwrap(), "db");
+        assert_eq!(std::fs::read_to_string(account.join("cache.sqlite-wal")).unwrap(), "wal");
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,328 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::{Condvar, Mutex};
+use std::time::Duration;
+
+use base64::Engine;
+
+use super::account_dir;
+use crate::compose::Draft;
+use crate::compose::builder::RawMessage;
+
+// give up after this many attempts; the message stays in the outbox as failed
+pub const MAX_ATTEMPTS: u32 = 8;
+const FIRST_BACKOFF_SECS: i64 = 30;
+const MAX_BACKOFF_SECS: i64 = 3600;
+// a claim older than this is abandoned even if its process id is in use again
+const SENDING_TIMEOUT_SECS: i64 = 600;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Pending,
+    /// handed to the API by a worker, possibly in another mailtui process; left alone by
+    /// edit/delete until it settles
+    Sending,
+    Failed,
+}
+
+/// A message waiting to be sent, one JSON file each under `<config>/outbox`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Entry {
+    pub id: String,
+    /// what was composed, for the outbox view and for editing
+    pub draft: Draft,
+    /// the message as built at enqueue time, base64; every attempt sends these same bytes
+    /// (same Message-ID and Date). Unset in entries queued by older versions.
+    #[serde(default)]
+    pub raw: Option<String>,
+    pub thread_id: Option<String>,
+    pub draft_id: Option<String>,
+    pub status: Status,
+    pub attempts: u32,
+    pub next_attempt_unix: i64,
+    pub last_error: Option<String>,
+    pub queued_unix: i64,
+    /// process id of the worker sending it, while `Sending`
+    #[serde(default)]
+    pub owner_pid: Option<u32>,
+    #[serde(default)]
+    pub claimed_unix: Option<i64>,
+}
+
+// workers and UIs of every running mailtui rewrite entries: the mutex orders threads of this
+// process, `.lock` (flock) orders processes
+static FILE_LOCK: Mutex<()> = Mutex::new(());
+// lets enqueue/retry wake the worker instead of waiting out its poll interval
+static WAKE: (Mutex<bool>, Condvar) = (Mutex::new(false), Condvar::new());
+
+fn outbox_dir() -> PathBuf {
+    let mut d = account_dir();
+    d.push("outbox");
+    d
+}
+
+fn entry_file(id: &str) -> PathBuf {
+    outbox_dir().join(format!("{}.json", id))
+}
+
+pub fn now_unix() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+fn write_entry(e: &Entry) -> io::Result<()> {
+    fs::create_dir_all(outbox_dir())?;
+    let tmp = entry_file(&e.id).with_extension("tmp");
+    let data = serde_json::to_string_pretty(e).map_err(io::Error::other)?;
+    let mut f = fs::File::create(&tmp)?;
+    f.write_all(data.as_bytes())?;
+    f.flush()?;
+    fs::rename(tmp, entry_file(&e.id))
+}
+
+fn read_entry(id: &str) -> Option<Entry> {
+    fs::read_to_string(entry_file(id)).ok().and_then(|s| serde_json::from_str(&s).ok())
+}
+
+struct Lock {
+    _file: fs::File,
+    _guard: std::sync::MutexGuard<'static, ()>,
+}
+
+// held while reading an entry and writing it back, so no two processes claim the same one
+fn locked() -> io::Result<Lock> {
+    let guard = FILE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    fs::create_dir_all(outbox_dir())?;
+    let file = fs::OpenOptions::new().create(true).truncate(false).write(true).open(outbox_dir().join(".lock"))?;
+    // released when the file is closed
+    file.lock()?;
+    Ok(Lock { _file: file, _guard: guard })
+}
+
+#[cfg(target_os = "linux")]
+fn process_alive(pid: u32) -> bool {
+    std::path::Path::new("/proc").join(pid.to_string()).exists()
+}
+
+// without a cheap liveness check, only the timeout frees a claim
+#[cfg(not(target_os = "linux"))]
+fn process_alive(_pid: u32) -> bool {
+    true
+}
+
+/// Wake the send worker.
+pub fn notify() {
+    let (flag, cvar) = &WAKE;
+    *flag.lock().unwrap_or_else(|e| e.into_inner()) = true;
+    cvar.notify_all();
+}
+
+/// Block until `notify` is called or `timeout` passes.
+pub fn wait(timeout: Duration) {
+    let (flag, cvar) = &WAKE;
+    let guard = flag.lock().unwrap_or_else(|e| e.into_inner());
+    let (mut woken, _) = cvar
+        .wait_timeout_while(guard, timeout, |woken| !*woken)
+        .unwrap_or_else(|e| e.into_inner());
+    *woken = false;
+}
+
+impl Entry {
+    /// Whether a worker of this process is sending it.
+    pub fn sending_here(&self) -> bool {
+        self.status == Status::Sending && self.owner_pid == Some(std::process::id())
+    }
+
+    /// A `Sending` claim whose process exited mid-send (or that timed out).
+    fn abandoned(&self, now: i64) -> bool {
+        if self.status != Status::Sending {
+            return false;
+        }
+        match (self.owner_pid, self.claimed_unix) {
+            (Some(pid), Some(at)) => now - at > SENDING_TIMEOUT_SECS || (pid != std::process::id() && !process_alive(pid)),
+            // claimed by a version that didn't record its owner
+            _ => true,
+        }
+    }
+
+    /// The message to send, if it was built yet.
+    pub fn message(&self) -> Option<RawMessage> {
+        let raw = self.raw.as_deref()?;
+        base64::engine::general_purpose::STANDARD.decode(raw).ok().map(RawMessage::from)
+    }
+
+    pub fn set_message(&mut self, raw: &RawMessage) {
+        self.raw = Some(base64::engine::general_purpose::STANDARD.encode(raw.as_bytes()));
+    }
+}
+
+/// Queue `raw`, built from `draft`, for sending. Returns the entry id.
+pub fn enqueue(draft: Draft, raw: &RawMessage) -> io::Result<String> {
+    let now = now_unix();
+    let id = format!("{}-{:016x}", now, crate::compose::builder::random_hex());
+    let mut entry = Entry {
+        id: id.clone(),
+        raw: None,
+        thread_id: draft.thread_id.clone(),
+        draft_id: draft.draft_id.clone(),
+        draft,
+        status: Status::Pending,
+        attempts: 0,
+        next_attempt_unix: now,
+        last_error: None,
+        queued_unix: now,
+        owner_pid: None,
+        claimed_unix: None,
+    };
+    entry.set_message(raw);
+    {
+        let _lock = locked()?;
+        write_entry(&entry)?;
+    }
+    notify();
+    Ok(id)
+}
+
+/// Everything in the outbox, oldest first. Entries are replaced by rename, so this reads
+/// without the lock.
+pub fn list() -> Vec<Entry> {
+    let Ok(dir) = fs::read_dir(outbox_dir()) else { return Vec::new() };
+    let mut entries: Vec<Entry> = dir
+        .flatten()
+        .filter(|d| d.path().extension().is_some_and(|e| e == "json"))
+        .filter_map(|d| fs::read_to_string(d.path()).ok())
+        .filter_map(|s| serde_json::from_str(&s).ok())
+        .collect();
+    entries.sort_by(|a, b| a.queued_unix.cmp(&b.queued_unix).then(a.id.cmp(&b.id)));
+    entries
+}
+
+/// Remove an entry for editing or deletion. Fails while a worker is sending it.
+pub fn take(id: &str) -> io::Result<Entry> {
+    let _lock = locked()?;
+    let entry = read_entry(id).ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no longer in the outbox"))?;
+    if entry.status == Status::Sending && !entry.abandoned(now_unix()) {
+        return Err(io::Error::other("message is being sent right now"));
+    }
+    fs::remove_file(entry_file(id))?;
+    Ok(entry)
+}
+
+/// Put a failed or backed-off entry back at the front of the queue.
+pub fn retry_now(id: &str) -> io::Result<()> {
+    {
+        let _lock = locked()?;
+        let Some(mut entry) = read_entry(id) else { return Ok(()) };
+        if entry.status == Status::Sending && !entry.abandoned(now_unix()) {
+            return Ok(());
+        }
+        entry.status = Status::Pending;
+        entry.attempts = 0;
+        entry.next_attempt_unix = now_unix();
+        write_entry(&entry)?;
+    }
+    notify();
+    Ok(())
+}
+
+/// Entries left `Sending` by a process that exited mid-send go back to pending. Claims of
+/// running processes are left alone, they may be talking to the API right now.
+pub fn recover() -> io::Result<()> {
+    let _lock = locked()?;
+    let now = now_unix();
+    for mut entry in list().into_iter().filter(|e| e.abandoned(now)) {
+        entry.status = Status::Pending;
+        entry.owner_pid = None;
+        entry.claimed_unix = None;
+        write_entry(&entry)?;
+    }
+    Ok(())
+}
+
+/// Claim the next entry that is due, marking it `Sending` by this process.
+pub fn claim_due() -> io::Result<Option<Entry>> {
+    let _lock = locked()?;
+    let now = now_unix();
+    let Some(mut entry) = list().into_iter().find(|e| e.status == Status::Pending && e.next_attempt_unix <= now) else {
+        return Ok(None);
+    };
+    entry.status = Status::Sending;
+    entry.owner_pid = Some(std::process::id());
+    entry.claimed_unix = Some(now);
+    write_entry(&entry)?;
+    Ok(Some(entry))
+}
+
+/// Record the outcome of a send. Confirmed messages are removed; failures are
+/// rescheduled with exponential backoff until `MAX_ATTEMPTS`.
+pub fn finish(mut entry: Entry, result: Result<(), String>) -> io::Result<()> {
+    let _lock = locked()?;
+    entry.owner_pid = None;
+    entry.claimed_unix = None;
+    match result {
+        Ok(()) => match fs::remove_file(entry_file(&entry.id)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        },
+        Err(err) => {
+            entry.attempts += 1;
+            entry.last_error = Some(err);
+            if entry.attempts >= MAX_ATTEMPTS {
+                entry.status = Status::Failed;
+            } else {
+                let backoff = FIRST_BACKOFF_SECS.saturating_mul(1 << (entry.attempts - 1).min(16)).min(MAX_BACKOFF_SECS);
+                entry.status = Status::Pending;
+                entry.next_attempt_unix = now_unix() + backoff;
+            }
+            write_entry(&entry)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sending(owner_pid: Option<u32>, claimed_unix: Option<i64>) -> Entry {
+        Entry {
+            id: "1".into(),
+            draft: Draft::default(),
+            raw: None,
+            thread_id: None,
+            draft_id: None,
+            status: Status::Sending,
+            attempts: 0,
+            next_attempt_unix: 0,
+            last_error: None,
+            queued_unix: 0,
+            owner_pid,
+            claimed_unix,
+        }
+    }
+
+    #[test]
+    fn only_abandoned_claims_are_recovered() {
+        let now = now_unix();
+        // a live sender, this test process
+        assert!(!sending(Some(std::process::id()), Some(now)).abandoned(now));
+        assert!(sending(Some(std::process::id()), Some(now - SENDING_TIMEOUT_SECS - 1)).abandoned(now));
+        // claimed by an older version
+        assert!(sending(None, None).abandoned(now));
+        let mut pending = sending(None, None);
+        pending.status = Status::Pending;
+        assert!(!pending.abandoned(now));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn claims_of_exited_processes_are_abandoned() {
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        let pid = child.id();
+        child.wait().unwrap();
+        assert!(sending(Some(pid), Some(now_unix())).abandoned(now_unix()));
+    }
+}
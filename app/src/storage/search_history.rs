@@ -0,0 +1,43 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use super::config_dir;
+
+// keep the file small; older queries fall off the end
+const MAX_ENTRIES: usize = 50;
+
+fn history_file() -> PathBuf {
+    let mut d = config_dir();
+    d.push("search_history.json");
+    d
+}
+
+/// Load saved search queries, most recent first. A missing file is an empty history.
+pub fn load_history() -> Vec<String> {
+    fs::read_to_string(history_file())
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+/// Move `query` to the front of the history and persist it.
+pub fn push_history(query: &str) -> io::Result<Vec<String>> {
+    let query = query.trim();
+    let mut history = load_history();
+    if query.is_empty() {
+        return Ok(history);
+    }
+    history.retain(|q| q != query);
+    history.insert(0, query.to_string());
+    history.truncate(MAX_ENTRIES);
+
+    fs::create_dir_all(config_dir())?;
+    let tmp = history_file().with_extension("tmp");
+    let data = serde_json::to_string_pretty(&history).map_err(io::Error::other)?;
+    let mut f = fs::File::create(&tmp)?;
+    f.write_all(data.as_bytes())?;
+    f.flush()?;
+    fs::rename(tmp, history_file())?;
+    Ok(history)
+}
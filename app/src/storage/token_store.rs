@@ -1,7 +1,9 @@
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::io::{self, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+use super::{account_dir, config_dir};
 
 #[derive(Serialize, Deserialize, Debug, Default)]
 pub struct SavedToken {
@@ -10,27 +12,19 @@ pub struct SavedToken {
     pub expires_at_unix: Option<i64>,
 }
 
-fn config_dir() -> PathBuf {
-    if let Some(dir) = dirs::config_dir() {
-        dir.join("mailtui")
-    } else {
-        let mut p = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
-        p.push(".config/mailtui");
-        p
-    }
+pub fn token_file_in(dir: &Path) -> PathBuf {
+    dir.join("token.json")
 }
 
 fn token_file() -> PathBuf {
-    let mut d = config_dir();
-    d.push("token.json");
-    d
+    token_file_in(&account_dir())
 }
 
 pub fn save_token(token: &SavedToken) -> io::Result<()> {
-    let dir = config_dir();
+    let dir = account_dir();
     fs::create_dir_all(&dir)?;
     let tmp = token_file().with_extension("tmp");
-    let data = serde_json::to_string_pretty(token).map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+    let data = serde_json::to_string_pretty(token).map_err(io::Error::other)?;
     let mut f = fs::File::create(&tmp)?;
     f.write_all(data.as_bytes())?;
     f.flush()?;
@@ -39,8 +33,29 @@ pub fn save_token(token: &SavedToken) -> io::Result<()> {
 }
 
 pub fn load_token() -> io::Result<SavedToken> {
-    let p = token_file();
+    let mut p = token_file();
+    // a login from before accounts were configured still works for the default one
+    if !p.exists() && crate::config::get().is_default_account() {
+        p = token_file_in(&config_dir());
+    }
     let s = fs::read_to_string(p)?;
-    let t: SavedToken = serde_json::from_str(&s).map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+    let t: SavedToken = serde_json::from_str(&s).map_err(io::Error::other)?;
     Ok(t)
 }
+
+/// Forget the saved token. Returns false if there was none.
+pub fn delete_token() -> io::Result<bool> {
+    let mut removed = false;
+    let mut files = vec![token_file()];
+    if crate::config::get().is_default_account() {
+        files.push(token_file_in(&config_dir()));
+    }
+    for f in files {
+        match fs::remove_file(f) {
+            Ok(()) => removed = true,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(removed)
+}
@@ -0,0 +1,280 @@
+//! Named color themes, with a style for each kind of element on screen.
+//!
+//! Built in: `dark` (the default), `light`, `high-contrast` and `16-color` for terminals
+//! without 256 colors. The `[theme]` section picks one with `base` and overrides single
+//! elements; `[themes.<name>]` defines new ones the same way:
+//!
+//! ```toml
+//! [theme]
+//! base = "paper"
+//! link = { fg = "cyan", underline = true }
+//!
+//! [themes.paper]
+//! base = "light"
+//! selection = { fg = "black", bg = "#ffd75f", bold = true }
+//! unread = { bold = true }
+//! ```
+//!
+//! Style keys are `fg` and `bg` (names, "#rrggbb" or a 0-255 index) and the flags `bold`,
+//! `dim`, `italic`, `underline` and `reversed`. When `NO_COLOR` is set, colors are dropped
+//! and only the flags remain.
+
+use ratatui::style::{Color, Modifier, Style};
+use serde::{Deserialize, Deserializer};
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+static THEME: OnceLock<Theme> = OnceLock::new();
+
+pub const BUILTIN: &[&str] = &["dark", "light", "high-contrast", "16-color"];
+
+// how deep `base` references may nest, so a cycle is an error rather than a hang
+const MAX_DEPTH: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    /// the selected row of a list
+    pub selection: Style,
+    pub unread: Style,
+    /// starred messages
+    pub flagged: Style,
+    /// From/To/Date lines of a message
+    pub header: Style,
+    /// quoted (`>`) lines of a message
+    pub quote: Style,
+    pub link: Style,
+    pub status_bar: Style,
+    /// notices, focused fields and the current search match
+    pub accent: Style,
+    pub error: Style,
+    pub dim: Style,
+}
+
+fn fg(c: Color) -> Style {
+    Style::default().fg(c)
+}
+
+const BOLD: Modifier = Modifier::BOLD;
+
+impl Theme {
+    pub fn dark() -> Theme {
+        Theme {
+            selection: fg(Color::Yellow).add_modifier(BOLD),
+            unread: Style::default().add_modifier(BOLD),
+            flagged: fg(Color::Indexed(208)),
+            header: fg(Color::Indexed(110)),
+            quote: fg(Color::Indexed(108)),
+            link: fg(Color::Indexed(75)).add_modifier(Modifier::UNDERLINED),
+            status_bar: fg(Color::Indexed(252)).bg(Color::Indexed(236)),
+            accent: fg(Color::Yellow),
+            error: fg(Color::Indexed(203)),
+            dim: fg(Color::Indexed(244)),
+        }
+    }
+
+    pub fn light() -> Theme {
+        Theme {
+            selection: fg(Color::Indexed(25)).add_modifier(BOLD),
+            unread: Style::default().add_modifier(BOLD),
+            flagged: fg(Color::Indexed(166)),
+            header: fg(Color::Indexed(24)),
+            quote: fg(Color::Indexed(65)),
+            link: fg(Color::Indexed(26)).add_modifier(Modifier::UNDERLINED),
+            status_bar: fg(Color::Indexed(235)).bg(Color::Indexed(252)),
+            accent: fg(Color::Indexed(130)),
+            error: fg(Color::Indexed(160)),
+            dim: fg(Color::Indexed(242)),
+        }
+    }
+
+    pub fn high_contrast() -> Theme {
+        Theme {
+            selection: fg(Color::Black).bg(Color::Yellow).add_modifier(BOLD),
+            unread: fg(Color::White).add_modifier(BOLD),
+            flagged: fg(Color::LightYellow).add_modifier(BOLD),
+            header: fg(Color::White).add_modifier(BOLD | Modifier::UNDERLINED),
+            quote: fg(Color::LightCyan),
+            link: fg(Color::LightCyan).add_modifier(BOLD | Modifier::UNDERLINED),
+            status_bar: fg(Color::Black).bg(Color::White),
+            accent: fg(Color::LightYellow).add_modifier(BOLD),
+            error: fg(Color::LightRed).add_modifier(BOLD),
+            dim: fg(Color::Gray),
+        }
+    }
+
+    /// Only the 16 ANSI colors.
+    pub fn ansi16() -> Theme {
+        Theme {
+            selection: fg(Color::Yellow).add_modifier(BOLD),
+            unread: Style::default().add_modifier(BOLD),
+            flagged: fg(Color::Magenta),
+            header: fg(Color::Cyan),
+            quote: fg(Color::Green),
+            link: fg(Color::Blue).add_modifier(Modifier::UNDERLINED),
+            status_bar: fg(Color::Black).bg(Color::Gray),
+            accent: fg(Color::Yellow),
+            error: fg(Color::Red),
+            dim: fg(Color::DarkGray),
+        }
+    }
+
+    pub fn builtin(name: &str) -> Option<Theme> {
+        match name {
+            "dark" => Some(Theme::dark()),
+            "light" => Some(Theme::light()),
+            "high-contrast" => Some(Theme::high_contrast()),
+            "16-color" => Some(Theme::ansi16()),
+            _ => None,
+        }
+    }
+
+    fn elements_mut(&mut self) -> [&mut Style; 10] {
+        [
+            &mut self.selection,
+            &mut self.unread,
+            &mut self.flagged,
+            &mut self.header,
+            &mut self.quote,
+            &mut self.link,
+            &mut self.status_bar,
+            &mut self.accent,
+            &mut self.error,
+            &mut self.dim,
+        ]
+    }
+
+    /// Drop every color, keeping bold, underline and the like. The selection turns to
+    /// reverse video so it stays visible.
+    pub fn without_colors(mut self) -> Theme {
+        for style in self.elements_mut() {
+            *style = Style::default().add_modifier(style.add_modifier);
+        }
+        self.selection = self.selection.add_modifier(Modifier::REVERSED);
+        self
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::dark()
+    }
+}
+
+/// One element's style in the config; unset keys keep the base theme's value.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct StyleDef {
+    #[serde(deserialize_with = "color")]
+    pub fg: Option<Color>,
+    #[serde(deserialize_with = "color")]
+    pub bg: Option<Color>,
+    pub bold: Option<bool>,
+    pub dim: Option<bool>,
+    pub italic: Option<bool>,
+    pub underline: Option<bool>,
+    pub reversed: Option<bool>,
+}
+
+impl StyleDef {
+    fn apply(&self, mut style: Style) -> Style {
+        if let Some(c) = self.fg {
+            style = style.fg(c);
+        }
+        if let Some(c) = self.bg {
+            style = style.bg(c);
+        }
+        for (flag, modifier) in [
+            (self.bold, Modifier::BOLD),
+            (self.dim, Modifier::DIM),
+            (self.italic, Modifier::ITALIC),
+            (self.underline, Modifier::UNDERLINED),
+            (self.reversed, Modifier::REVERSED),
+        ] {
+            style = match flag {
+                Some(true) => style.add_modifier(modifier),
+                Some(false) => style.remove_modifier(modifier),
+                None => style,
+            };
+        }
+        style
+    }
+}
+
+/// `[theme]` or one of `[themes.<name>]`: a base theme and per-element overrides.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThemeDef {
+    /// a built-in theme or another `[themes]` entry; `dark` when unset
+    pub base: Option<String>,
+    pub selection: Option<StyleDef>,
+    pub unread: Option<StyleDef>,
+    pub flagged: Option<StyleDef>,
+    pub header: Option<StyleDef>,
+    pub quote: Option<StyleDef>,
+    pub link: Option<StyleDef>,
+    pub status_bar: Option<StyleDef>,
+    pub accent: Option<StyleDef>,
+    pub error: Option<StyleDef>,
+    pub dim: Option<StyleDef>,
+}
+
+impl ThemeDef {
+    fn overrides(&self) -> [&Option<StyleDef>; 10] {
+        [
+            &self.selection,
+            &self.unread,
+            &self.flagged,
+            &self.header,
+            &self.quote,
+            &self.link,
+            &self.status_bar,
+            &self.accent,
+            &self.error,
+            &self.dim,
+        ]
+    }
+
+    /// The theme this describes, looking `base` up in `themes` before the built-ins.
+    pub fn resolve(&self, themes: &BTreeMap<String, ThemeDef>) -> Result<Theme, String> {
+        self.resolve_at(None, themes, 0)
+    }
+
+    // `own` is this entry's name in `themes`: [themes.dark] without a base builds on the built-in dark
+    fn resolve_at(&self, own: Option<&str>, themes: &BTreeMap<String, ThemeDef>, depth: usize) -> Result<Theme, String> {
+        let base = self.base.as_deref().unwrap_or("dark");
+        let mut theme = match themes.get(base).filter(|_| own != Some(base)) {
+            Some(_) if depth >= MAX_DEPTH => return Err(format!("theme {:?}: too many nested bases (a cycle?)", base)),
+            Some(def) => def.resolve_at(Some(base), themes, depth + 1)?,
+            None => Theme::builtin(base).ok_or_else(|| {
+                let mut known: Vec<&str> = BUILTIN.to_vec();
+                known.extend(themes.keys().map(String::as_str).filter(|k| !BUILTIN.contains(k)));
+                format!("unknown theme {:?} (available: {})", base, known.join(", "))
+            })?,
+        };
+        let overrides = self.overrides();
+        for (style, def) in theme.elements_mut().into_iter().zip(overrides) {
+            if let Some(def) = def {
+                *style = def.apply(*style);
+            }
+        }
+        Ok(theme)
+    }
+}
+
+fn color<'de, D: Deserializer<'de>>(d: D) -> Result<Option<Color>, D::Error> {
+    let s = String::deserialize(d)?;
+    s.parse().map(Some).map_err(|_| serde::de::Error::custom(format!("unknown color {:?}", s)))
+}
+
+// NO_COLOR counts when set to anything non-empty, per no-color.org
+fn no_color() -> bool {
+    std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty())
+}
+
+/// The theme from the config, without colors under `NO_COLOR`.
+pub fn get() -> &'static Theme {
+    THEME.get_or_init(|| {
+        let theme = crate::config::get().theme().unwrap_or_default();
+        if no_color() { theme.without_colors() } else { theme }
+    })
+}
@@ -1,3 +1 @@
 pub use crate::storage::token_store::*;
-pub use crate::storage::token_store::*;
-use serde::{Deserialize, Serialize};
@@ -0,0 +1,346 @@
+//! Columns of the message list, and fitting text into them by display width, so wide (CJK,
+//! emoji) and zero-width characters keep the columns aligned.
+//!
+//! `[layout] columns` lists them left to right, each as a field name, optionally with a
+//! width in cells: `["flags", "sender:30", "subject", "labels", "date"]`. The subject takes
+//! whatever space the others leave unless it is given a width too.
+
+use chrono::{DateTime, Datelike, Local, TimeZone};
+use std::str::FromStr;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use super::{ListMode, ThreadSummary, display_name};
+use crate::gmail::{LabelNames, SimpleMail, label_name};
+
+// a flexible column never gets squeezed below this while fixed ones can still give up space
+const MIN_FLEXIBLE: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    /// unread and starred markers
+    Flags,
+    Sender,
+    Subject,
+    Date,
+    Size,
+    Attachment,
+    Labels,
+    /// messages of the thread loaded so far; only shown in thread mode
+    Count,
+}
+
+const FIELDS: &[(&str, Field)] = &[
+    ("flags", Field::Flags),
+    ("sender", Field::Sender),
+    ("subject", Field::Subject),
+    ("date", Field::Date),
+    ("size", Field::Size),
+    ("attachment", Field::Attachment),
+    ("labels", Field::Labels),
+    ("count", Field::Count),
+];
+
+impl Field {
+    // None: shares the space left over
+    fn default_width(self) -> Option<usize> {
+        match self {
+            Field::Flags => Some(1),
+            Field::Sender => Some(22),
+            Field::Subject => None,
+            Field::Date => Some(10),
+            Field::Size => Some(5),
+            Field::Attachment => Some(2),
+            Field::Labels => Some(16),
+            Field::Count => Some(3),
+        }
+    }
+
+    fn title(self) -> &'static str {
+        match self {
+            Field::Flags | Field::Attachment => "",
+            Field::Sender => "From",
+            Field::Subject => "Subject",
+            Field::Date => "Date",
+            Field::Size => "Size",
+            Field::Labels => "Labels",
+            Field::Count => "#",
+        }
+    }
+
+    fn right_aligned(self) -> bool {
+        matches!(self, Field::Size | Field::Count)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Column {
+    pub field: Field,
+    /// cells; unset means the field's default
+    pub width: Option<usize>,
+}
+
+impl Column {
+    pub fn defaults() -> Vec<Column> {
+        [Field::Flags, Field::Sender, Field::Count, Field::Subject, Field::Attachment, Field::Date]
+            .into_iter()
+            .map(|field| Column { field, width: None })
+            .collect()
+    }
+
+    fn fixed_width(&self) -> Option<usize> {
+        self.width.or(self.field.default_width())
+    }
+}
+
+impl FromStr for Column {
+    type Err = String;
+
+    /// `name` or `name:width`.
+    fn from_str(s: &str) -> Result<Column, String> {
+        let (name, width) = match s.split_once(':') {
+            Some((name, w)) => {
+                let w: usize = w.trim().parse().map_err(|_| format!("invalid width {:?} for column {:?}", w, name))?;
+                (name.trim(), Some(w.max(1)))
+            }
+            None => (s.trim(), None),
+        };
+        let field = FIELDS.iter().find(|(n, _)| *n == name).map(|(_, f)| *f).ok_or_else(|| {
+            let names: Vec<&str> = FIELDS.iter().map(|(n, _)| *n).collect();
+            format!("unknown column {:?}, expected one of {}", name, names.join(", "))
+        })?;
+        Ok(Column { field, width })
+    }
+}
+
+/// The columns that apply in `mode`: the thread count only makes sense for threads.
+pub fn visible(columns: &[Column], mode: ListMode) -> Vec<Column> {
+    columns.iter().copied().filter(|c| c.field != Field::Count || mode == ListMode::Threads).collect()
+}
+
+/// Cell widths for `columns` in `total` cells with a space between columns. Flexible columns
+/// share what the fixed ones leave; when that is too little, the widest fixed columns shrink first.
+pub fn widths(columns: &[Column], total: usize) -> Vec<usize> {
+    let available = total.saturating_sub(columns.len().saturating_sub(1));
+    let mut widths: Vec<usize> = columns.iter().map(|c| c.fixed_width().unwrap_or(0)).collect();
+    let flexible: Vec<usize> = (0..columns.len()).filter(|i| columns[*i].fixed_width().is_none()).collect();
+
+    let wanted = available.min(MIN_FLEXIBLE * flexible.len());
+    while widths.iter().sum::<usize>() + wanted > available {
+        let (widest, w) = widths.iter().copied().enumerate().max_by_key(|(_, w)| *w).unwrap_or((0, 0));
+        if w <= 1 {
+            break;
+        }
+        widths[widest] -= 1;
+    }
+
+    let rest = available.saturating_sub(widths.iter().sum());
+    for (n, i) in flexible.iter().enumerate() {
+        // the first ones get the remainder
+        widths[*i] = rest / flexible.len() + usize::from(n < rest % flexible.len());
+    }
+    widths
+}
+
+/// `text` cut to at most `width` cells, with `…` marking the cut. Control characters
+/// (tabs, newlines of folded headers) become spaces.
+pub fn truncate(text: &str, width: usize) -> String {
+    let text: String = text.chars().map(|c| if c.is_control() { ' ' } else { c }).collect();
+    if text.width() <= width {
+        return text;
+    }
+    let mut out = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        let w = c.width().unwrap_or(0);
+        // leave a cell for the ellipsis
+        if used + w >= width {
+            break;
+        }
+        out.push(c);
+        used += w;
+    }
+    if width > 0 {
+        out.push('…');
+    }
+    out
+}
+
+/// `text` truncated, then padded with spaces to exactly `width` cells.
+pub fn fit(text: &str, width: usize, right: bool) -> String {
+    let text = truncate(text, width);
+    let pad = " ".repeat(width.saturating_sub(text.width()));
+    if right { pad + &text } else { text + &pad }
+}
+
+/// One line of column titles.
+pub fn header(columns: &[Column], widths: &[usize]) -> String {
+    let cells: Vec<String> = columns.iter().zip(widths).map(|(c, w)| fit(c.field.title(), *w, c.field.right_aligned())).collect();
+    cells.join(" ")
+}
+
+/// What the list shows of a message or a thread.
+pub struct RowData {
+    pub sender: String,
+    pub subject: String,
+    pub date: String,
+    pub size: Option<u64>,
+    pub attachment: bool,
+    pub labels: Vec<String>,
+    pub count: usize,
+    pub unread: bool,
+    pub starred: bool,
+}
+
+impl RowData {
+    pub fn message(m: &SimpleMail, names: &LabelNames) -> RowData {
+        RowData {
+            sender: display_name(m.from.as_deref().unwrap_or("unknown")),
+            subject: m.subject.clone().unwrap_or_else(|| "(no subject)".into()),
+            date: short_date(m.internal_date, m.date.as_deref(), Local::now()),
+            size: m.size.map(u64::from),
+            attachment: m.has_attachment,
+            labels: m.labels.iter().map(|l| label_name(l, names)).collect(),
+            count: 1,
+            unread: m.unread,
+            starred: m.starred,
+        }
+    }
+
+    pub fn thread(t: &ThreadSummary, names: &LabelNames) -> RowData {
+        RowData {
+            sender: t.participants.join(", "),
+            subject: t.subject.clone().unwrap_or_else(|| "(no subject)".into()),
+            date: short_date(t.latest.internal_date, t.date.as_deref(), Local::now()),
+            size: t.size,
+            attachment: t.has_attachment,
+            labels: t.labels.iter().map(|l| label_name(l, names)).collect(),
+            count: t.count,
+            unread: t.unread,
+            starred: t.starred,
+        }
+    }
+
+    fn cell(&self, field: Field) -> String {
+        match field {
+            Field::Flags if self.starred => "★".into(),
+            Field::Flags if self.unread => "●".into(),
+            Field::Flags => "○".into(),
+            Field::Sender => self.sender.clone(),
+            Field::Subject => self.subject.clone(),
+            Field::Date => self.date.clone(),
+            Field::Size => self.size.map(human_size).unwrap_or_default(),
+            Field::Attachment if self.attachment => "📎".into(),
+            Field::Attachment => String::new(),
+            Field::Labels => self.labels.join(", "),
+            Field::Count => self.count.to_string(),
+        }
+    }
+
+    /// The row's cells fitted to `widths`, a space apart.
+    pub fn line(&self, columns: &[Column], widths: &[usize]) -> String {
+        let cells: Vec<String> =
+            columns.iter().zip(widths).map(|(c, w)| fit(&self.cell(c.field), *w, c.field.right_aligned())).collect();
+        cells.join(" ")
+    }
+}
+
+// time for today, day and month for this year, the full date before that
+fn short_date(internal_ms: Option<i64>, header: Option<&str>, now: DateTime<Local>) -> String {
+    let time = internal_ms
+        .and_then(|ms| Local.timestamp_millis_opt(ms).single())
+        .or_else(|| header.and_then(|h| DateTime::parse_from_rfc2822(h).ok()).map(|d| d.with_timezone(&Local)));
+    match time {
+        Some(t) if t.date_naive() == now.date_naive() => t.format("%H:%M").to_string(),
+        Some(t) if t.year() == now.year() => t.format("%b %d").to_string(),
+        Some(t) => t.format("%Y-%m-%d").to_string(),
+        None => header.unwrap_or_default().to_string(),
+    }
+}
+
+// 512B, 4.2K, 37K, 1.5M
+fn human_size(bytes: u64) -> String {
+    const UNITS: &[&str] = &["B", "K", "M", "G"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 || size >= 10.0 {
+        format!("{:.0}{}", size, UNITS[unit])
+    } else {
+        format!("{:.1}{}", size, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_text_is_kept() {
+        assert_eq!(truncate("hello", 10), "hello");
+        assert_eq!(truncate("hello", 5), "hello");
+    }
+
+    #[test]
+    fn long_text_gets_an_ellipsis() {
+        assert_eq!(truncate("hello world", 8), "hello w…");
+        assert_eq!(truncate("hello world", 8).width(), 8);
+        assert_eq!(truncate("hello", 1), "…");
+        assert_eq!(truncate("hello", 0), "");
+    }
+
+    #[test]
+    fn wide_characters_count_two_cells() {
+        // each of these takes two cells
+        assert_eq!(truncate("日本語のテキスト", 7), "日本語…");
+        // the third character would end past the cut, so it goes and the result is a cell short
+        assert_eq!(truncate("日本語のテキスト", 6), "日本…");
+        assert_eq!(fit("日本語のテキスト", 6, false), "日本… ");
+        assert_eq!(truncate("👋👋👋 hi", 5), "👋👋…");
+    }
+
+    #[test]
+    fn zero_width_characters_take_no_cells() {
+        // e + combining acute accent
+        let text = "cafe\u{301} au lait";
+        assert_eq!(truncate(text, 12), text);
+        assert_eq!(truncate(text, 5), "cafe\u{301}…");
+    }
+
+    #[test]
+    fn control_characters_become_spaces() {
+        assert_eq!(truncate("a\tb\nc", 10), "a b c");
+    }
+
+    #[test]
+    fn fit_pads_to_the_width() {
+        assert_eq!(fit("ab", 4, false), "ab  ");
+        assert_eq!(fit("12", 4, true), "  12");
+        assert_eq!(fit("日本", 5, false), "日本 ");
+        for text in ["Zoë", "李小龙 <li@example.com>", "🎉 launch", "plain"] {
+            for width in 0..12 {
+                assert_eq!(fit(text, width, false).width(), width, "{:?} in {}", text, width);
+            }
+        }
+    }
+
+    #[test]
+    fn flexible_columns_take_the_rest() {
+        let columns: Vec<Column> = ["flags", "sender:10", "subject", "date"].iter().map(|c| c.parse().unwrap()).collect();
+        // 3 gaps, 1 + 10 + 10 fixed
+        assert_eq!(widths(&columns, 50), vec![1, 10, 26, 10]);
+        // too narrow: the fixed columns give way
+        let narrow = widths(&columns, 30);
+        assert_eq!(narrow[2], MIN_FLEXIBLE);
+        assert_eq!(narrow.iter().sum::<usize>() + 3, 30);
+    }
+
+    #[test]
+    fn column_names_are_checked() {
+        assert_eq!("size:8".parse::<Column>(), Ok(Column { field: Field::Size, width: Some(8) }));
+        assert!("sender:wide".parse::<Column>().is_err());
+        assert!("recipient".parse::<Column>().is_err());
+    }
+}
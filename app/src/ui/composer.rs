@@ -0,0 +1,601 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    Frame,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, List, ListItem, Paragraph},
+};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::compose::Draft;
+use crate::keymap::{Action, Context, Dispatcher};
+use crate::storage::contacts::ContactBook;
+
+const FIELD_NAMES: [&str; 4] = ["To", "Cc", "Bcc", "Subject"];
+const MAX_UNDO: usize = 200;
+// To, Cc and Bcc take addresses and get autocompletion
+const RECIPIENT_FIELDS: usize = 3;
+const MAX_SUGGESTIONS: usize = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Focus {
+    Field(usize),
+    Body,
+}
+
+// consecutive edits of the same kind are undone together
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EditKind {
+    Insert,
+    Delete,
+    Other,
+}
+
+#[derive(Clone)]
+struct Snapshot {
+    fields: [String; 4],
+    body: Vec<String>,
+    focus: Focus,
+    row: usize,
+    col: usize,
+}
+
+pub enum ComposerAction {
+    None,
+    /// editing finished; go on to the review screen
+    Done,
+}
+
+/// In-TUI alternative to `$EDITOR`: header fields plus a word-wrapping body editor.
+pub struct Composer {
+    fields: [String; 4],
+    body: Vec<String>,
+    focus: Focus,
+    // cursor: column within the focused field, or row/column within the body (in chars)
+    field_col: usize,
+    row: usize,
+    col: usize,
+    scroll: usize,
+    undo: Vec<Snapshot>,
+    last_edit: Option<EditKind>,
+    contacts: ContactBook,
+    // completions for the recipient being typed, and the highlighted one
+    suggestions: Vec<String>,
+    suggestion_sel: usize,
+    keys: Dispatcher,
+    help: bool,
+}
+
+fn byte_idx(s: &str, col: usize) -> usize {
+    s.char_indices().nth(col).map(|(i, _)| i).unwrap_or(s.len())
+}
+
+fn char_len(s: &str) -> usize {
+    s.chars().count()
+}
+
+impl Composer {
+    pub fn new(draft: &Draft, contacts: ContactBook) -> Self {
+        let fields = [draft.to.clone(), draft.cc.clone(), draft.bcc.clone(), draft.subject.clone()];
+        let mut body: Vec<String> = draft.body.lines().map(str::to_string).collect();
+        if body.is_empty() {
+            body.push(String::new());
+        }
+        // new messages start in To; replies already have recipients, so start in the body
+        let focus = if fields[0].is_empty() { Focus::Field(0) } else { Focus::Body };
+        let field_col = char_len(&fields[0]);
+        Composer {
+            fields,
+            body,
+            focus,
+            field_col,
+            row: 0,
+            col: 0,
+            scroll: 0,
+            undo: Vec::new(),
+            last_edit: None,
+            contacts,
+            suggestions: Vec::new(),
+            suggestion_sel: 0,
+            keys: Dispatcher::default(),
+            help: false,
+        }
+    }
+
+    /// Copy the edited header fields and body back into `draft`.
+    pub fn apply_to(&self, draft: &mut Draft) {
+        draft.to = self.fields[0].trim().to_string();
+        draft.cc = self.fields[1].trim().to_string();
+        draft.bcc = self.fields[2].trim().to_string();
+        draft.subject = self.fields[3].trim().to_string();
+        draft.body = self.body.join("\n");
+    }
+
+    fn snapshot(&mut self, kind: EditKind) {
+        if kind != EditKind::Other && self.last_edit == Some(kind) {
+            return;
+        }
+        self.last_edit = Some(kind);
+        self.undo.push(Snapshot {
+            fields: self.fields.clone(),
+            body: self.body.clone(),
+            focus: self.focus,
+            row: self.row,
+            col: self.col,
+        });
+        if self.undo.len() > MAX_UNDO {
+            self.undo.remove(0);
+        }
+    }
+
+    fn undo(&mut self) {
+        if let Some(s) = self.undo.pop() {
+            self.fields = s.fields;
+            self.body = s.body;
+            self.focus = s.focus;
+            self.row = s.row;
+            self.col = s.col;
+            if let Focus::Field(i) = self.focus {
+                self.field_col = char_len(&self.fields[i]);
+            }
+        }
+        self.last_edit = None;
+    }
+
+    // start of the address being typed: just after the last comma before the cursor
+    fn recipient_token_start(&self, field: usize) -> usize {
+        let before = &self.fields[field][..byte_idx(&self.fields[field], self.field_col)];
+        before.rfind(',').map(|i| i + 1).unwrap_or(0)
+    }
+
+    fn update_suggestions(&mut self) {
+        self.suggestions.clear();
+        self.suggestion_sel = 0;
+        if let Focus::Field(i) = self.focus
+            && i < RECIPIENT_FIELDS
+        {
+            let start = self.recipient_token_start(i);
+            let end = byte_idx(&self.fields[i], self.field_col);
+            let token = self.fields[i][start..end].trim();
+            self.suggestions = self
+                .contacts
+                .suggest(token, MAX_SUGGESTIONS)
+                .into_iter()
+                .map(|c| c.address())
+                .collect();
+        }
+    }
+
+    fn accept_suggestion(&mut self) {
+        let Focus::Field(i) = self.focus else { return };
+        let Some(addr) = self.suggestions.get(self.suggestion_sel).cloned() else { return };
+        self.snapshot(EditKind::Other);
+        let start = self.recipient_token_start(i);
+        let end = byte_idx(&self.fields[i], self.field_col);
+        let lead = if start == 0 { "" } else { " " };
+        let replacement = format!("{}{}, ", lead, addr);
+        self.fields[i].replace_range(start..end, &replacement);
+        self.field_col = char_len(&self.fields[i][..start]) + char_len(&replacement);
+        self.suggestions.clear();
+    }
+
+    fn set_focus(&mut self, focus: Focus) {
+        self.focus = focus;
+        self.last_edit = None;
+        if let Focus::Field(i) = focus {
+            self.field_col = char_len(&self.fields[i]);
+        }
+    }
+
+    fn focus_next(&mut self) {
+        let next = match self.focus {
+            Focus::Field(i) if i + 1 < FIELD_NAMES.len() => Focus::Field(i + 1),
+            Focus::Field(_) => Focus::Body,
+            Focus::Body => Focus::Field(0),
+        };
+        self.set_focus(next);
+    }
+
+    fn focus_prev(&mut self) {
+        let prev = match self.focus {
+            Focus::Field(0) => Focus::Body,
+            Focus::Field(i) => Focus::Field(i - 1),
+            Focus::Body => Focus::Field(FIELD_NAMES.len() - 1),
+        };
+        self.set_focus(prev);
+    }
+
+    fn insert_char(&mut self, c: char) {
+        // a word boundary closes the current undo group
+        self.snapshot(if c.is_whitespace() { EditKind::Other } else { EditKind::Insert });
+        match self.focus {
+            Focus::Field(i) => {
+                let at = byte_idx(&self.fields[i], self.field_col);
+                self.fields[i].insert(at, c);
+                self.field_col += 1;
+            }
+            Focus::Body => {
+                let line = &mut self.body[self.row];
+                let at = byte_idx(line, self.col);
+                line.insert(at, c);
+                self.col += 1;
+            }
+        }
+    }
+
+    fn newline(&mut self) {
+        match self.focus {
+            Focus::Field(_) => self.focus_next(),
+            Focus::Body => {
+                self.snapshot(EditKind::Other);
+                let line = &mut self.body[self.row];
+                let rest = line.split_off(byte_idx(line, self.col));
+                self.body.insert(self.row + 1, rest);
+                self.row += 1;
+                self.col = 0;
+            }
+        }
+    }
+
+    fn backspace(&mut self) {
+        match self.focus {
+            Focus::Field(i) => {
+                if self.field_col > 0 {
+                    self.snapshot(EditKind::Delete);
+                    let at = byte_idx(&self.fields[i], self.field_col - 1);
+                    self.fields[i].remove(at);
+                    self.field_col -= 1;
+                }
+            }
+            Focus::Body => {
+                if self.col > 0 {
+                    self.snapshot(EditKind::Delete);
+                    let line = &mut self.body[self.row];
+                    let at = byte_idx(line, self.col - 1);
+                    line.remove(at);
+                    self.col -= 1;
+                } else if self.row > 0 {
+                    self.snapshot(EditKind::Other);
+                    let line = self.body.remove(self.row);
+                    self.row -= 1;
+                    self.col = char_len(&self.body[self.row]);
+                    self.body[self.row].push_str(&line);
+                }
+            }
+        }
+    }
+
+    fn delete(&mut self) {
+        match self.focus {
+            Focus::Field(i) => {
+                if self.field_col < char_len(&self.fields[i]) {
+                    self.snapshot(EditKind::Delete);
+                    let at = byte_idx(&self.fields[i], self.field_col);
+                    self.fields[i].remove(at);
+                }
+            }
+            Focus::Body => {
+                if self.col < char_len(&self.body[self.row]) {
+                    self.snapshot(EditKind::Delete);
+                    let line = &mut self.body[self.row];
+                    let at = byte_idx(line, self.col);
+                    line.remove(at);
+                } else if self.row + 1 < self.body.len() {
+                    self.snapshot(EditKind::Other);
+                    let next = self.body.remove(self.row + 1);
+                    self.body[self.row].push_str(&next);
+                }
+            }
+        }
+    }
+
+    /// Insert a (possibly large, multi-line) pasted text as a single undo step.
+    pub fn paste(&mut self, text: &str) {
+        self.snapshot(EditKind::Other);
+        self.last_edit = None;
+        let text = text.replace("\r\n", "\n").replace('\r', "\n");
+        match self.focus {
+            // header fields are single-line
+            Focus::Field(i) => {
+                let flat = text.replace('\n', " ");
+                let at = byte_idx(&self.fields[i], self.field_col);
+                self.fields[i].insert_str(at, &flat);
+                self.field_col += char_len(&flat);
+            }
+            Focus::Body => {
+                let line = &mut self.body[self.row];
+                let tail = line.split_off(byte_idx(line, self.col));
+                let mut pieces = text.split('\n');
+                if let Some(first) = pieces.next() {
+                    self.body[self.row].push_str(first);
+                    self.col += char_len(first);
+                }
+                for piece in pieces {
+                    self.row += 1;
+                    self.body.insert(self.row, piece.to_string());
+                    self.col = char_len(piece);
+                }
+                self.body[self.row].push_str(&tail);
+            }
+        }
+    }
+
+    fn move_cursor(&mut self, key: KeyCode) {
+        self.last_edit = None;
+        match self.focus {
+            Focus::Field(i) => match key {
+                KeyCode::Left => self.field_col = self.field_col.saturating_sub(1),
+                KeyCode::Right => self.field_col = (self.field_col + 1).min(char_len(&self.fields[i])),
+                KeyCode::Home => self.field_col = 0,
+                KeyCode::End => self.field_col = char_len(&self.fields[i]),
+                KeyCode::Up => self.focus_prev(),
+                KeyCode::Down => self.focus_next(),
+                _ => {}
+            },
+            Focus::Body => match key {
+                KeyCode::Left => {
+                    if self.col > 0 {
+                        self.col -= 1;
+                    } else if self.row > 0 {
+                        self.row -= 1;
+                        self.col = char_len(&self.body[self.row]);
+                    }
+                }
+                KeyCode::Right => {
+                    if self.col < char_len(&self.body[self.row]) {
+                        self.col += 1;
+                    } else if self.row + 1 < self.body.len() {
+                        self.row += 1;
+                        self.col = 0;
+                    }
+                }
+                KeyCode::Up => {
+                    if self.row == 0 {
+                        self.set_focus(Focus::Field(FIELD_NAMES.len() - 1));
+                    } else {
+                        self.row -= 1;
+                        self.col = self.col.min(char_len(&self.body[self.row]));
+                    }
+                }
+                KeyCode::Down if self.row + 1 < self.body.len() => {
+                    self.row += 1;
+                    self.col = self.col.min(char_len(&self.body[self.row]));
+                }
+                KeyCode::Home => self.col = 0,
+                KeyCode::End => self.col = char_len(&self.body[self.row]),
+                KeyCode::PageUp => self.row = self.row.saturating_sub(10),
+                KeyCode::PageDown => self.row = (self.row + 10).min(self.body.len() - 1),
+                _ => {}
+            },
+        }
+        if let Focus::Body = self.focus {
+            self.col = self.col.min(char_len(&self.body[self.row]));
+        }
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> ComposerAction {
+        if self.help {
+            self.help = false;
+            return ComposerAction::None;
+        }
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+
+        // the completion popup takes navigation keys while it is open
+        if !self.suggestions.is_empty() {
+            let n = self.suggestions.len();
+            let next = (self.suggestion_sel + 1) % n;
+            let prev = (self.suggestion_sel + n - 1) % n;
+            let moved = match key.code {
+                KeyCode::Esc => {
+                    self.suggestions.clear();
+                    return ComposerAction::None;
+                }
+                KeyCode::Tab | KeyCode::Enter => {
+                    self.accept_suggestion();
+                    return ComposerAction::None;
+                }
+                KeyCode::Down => Some(next),
+                KeyCode::Char('n') if ctrl => Some(next),
+                KeyCode::Up => Some(prev),
+                KeyCode::Char('p') if ctrl => Some(prev),
+                _ => None,
+            };
+            if let Some(sel) = moved {
+                self.suggestion_sel = sel;
+                return ComposerAction::None;
+            }
+        }
+
+        // bound keys first; the rest edit the text and move the cursor
+        match self.keys.feed(&crate::config::get().keybindings, Context::Composer, key).first() {
+            Some(Action::Done) => return ComposerAction::Done,
+            Some(Action::Undo) => self.undo(),
+            Some(Action::NextField) => self.focus_next(),
+            Some(Action::PrevField) => self.focus_prev(),
+            Some(Action::Help) => self.help = true,
+            Some(_) => {}
+            None if self.keys.pending().is_some() => return ComposerAction::None,
+            None => match key.code {
+                KeyCode::Enter => self.newline(),
+                KeyCode::Backspace => self.backspace(),
+                KeyCode::Delete => self.delete(),
+                KeyCode::Char(c) if !ctrl => self.insert_char(c),
+                other => self.move_cursor(other),
+            },
+        }
+        match key.code {
+            KeyCode::Char(_) | KeyCode::Backspace | KeyCode::Delete if !ctrl => self.update_suggestions(),
+            _ => self.suggestions.clear(),
+        }
+        ComposerAction::None
+    }
+
+    pub fn render(&mut self, frame: &mut Frame) {
+        let size = frame.size();
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(FIELD_NAMES.len() as u16 + 2), Constraint::Min(3)].as_ref())
+            .split(size);
+
+        let label = Style::default().add_modifier(Modifier::BOLD);
+        let focused = crate::theme::get().selection;
+        let header_lines: Vec<Line> = FIELD_NAMES
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                let style = if self.focus == Focus::Field(i) { focused } else { label };
+                Line::from(vec![Span::styled(format!("{:<9}", format!("{}:", name)), style), Span::raw(self.fields[i].clone())])
+            })
+            .collect();
+        let headers = Paragraph::new(header_lines)
+            .block(Block::default().title(format!("Compose — {}", hints())).borders(Borders::ALL));
+        frame.render_widget(headers, chunks[0]);
+
+        let body_area = chunks[1];
+        let inner = Rect {
+            x: body_area.x + 1,
+            y: body_area.y + 1,
+            width: body_area.width.saturating_sub(2),
+            height: body_area.height.saturating_sub(2),
+        };
+        let (visual, cursor) = wrap_lines(&self.body, inner.width.max(1) as usize, self.row, self.col);
+
+        // keep the cursor line inside the viewport
+        let height = inner.height.max(1) as usize;
+        if cursor.0 < self.scroll {
+            self.scroll = cursor.0;
+        } else if cursor.0 >= self.scroll + height {
+            self.scroll = cursor.0 + 1 - height;
+        }
+
+        let lines: Vec<Line> = visual.into_iter().skip(self.scroll).take(height).map(Line::from).collect();
+        let body = Paragraph::new(lines).block(Block::default().title("Body").borders(Borders::ALL));
+        frame.render_widget(body, body_area);
+
+        if let Focus::Field(i) = self.focus
+            && !self.suggestions.is_empty()
+        {
+            let x = chunks[0].x + 10;
+            let width = self.suggestions.iter().map(|s| s.width()).max().unwrap_or(0) as u16 + 4;
+            let popup = Rect {
+                x,
+                y: chunks[0].y + 2 + i as u16,
+                width: width.min(size.width.saturating_sub(x)),
+                height: (self.suggestions.len() as u16 + 2).min(size.height.saturating_sub(chunks[0].y + 2 + i as u16)),
+            };
+            let items: Vec<ListItem> = self
+                .suggestions
+                .iter()
+                .enumerate()
+                .map(|(n, s)| {
+                    let style = if n == self.suggestion_sel { focused } else { Style::default() };
+                    ListItem::new(Span::styled(s.clone(), style))
+                })
+                .collect();
+            frame.render_widget(Clear, popup);
+            frame.render_widget(List::new(items).block(Block::default().borders(Borders::ALL)), popup);
+        }
+
+        match self.focus {
+            Focus::Field(i) => {
+                let field = &self.fields[i];
+                let x = chunks[0].x + 1 + 9 + field[..byte_idx(field, self.field_col)].width() as u16;
+                frame.set_cursor(x.min(chunks[0].right().saturating_sub(2)), chunks[0].y + 1 + i as u16);
+            }
+            Focus::Body => {
+                frame.set_cursor(inner.x + cursor.1 as u16, inner.y + (cursor.0 - self.scroll) as u16);
+            }
+        }
+
+        if self.help {
+            super::help::render(frame, "Composer keys", Context::Composer);
+        }
+    }
+}
+
+fn hints() -> String {
+    super::help::hints(
+        Context::Composer,
+        &[(Action::NextField, "next field"), (Action::Undo, "undo"), (Action::Done, "done"), (Action::Help, "help")],
+    )
+}
+
+/// Soft-wrap `lines` to `width` terminal cells, breaking after the last space where possible.
+/// Returns the visual lines and the visual (line, cell) of the logical cursor.
+fn wrap_lines(lines: &[String], width: usize, row: usize, col: usize) -> (Vec<String>, (usize, usize)) {
+    let width = width.max(1);
+    let cells = |chars: &[char]| chars.iter().map(|c| c.width().unwrap_or(0)).sum::<usize>();
+    let mut out = Vec::new();
+    let mut cursor = (0, 0);
+    for (r, line) in lines.iter().enumerate() {
+        let chars: Vec<char> = line.chars().collect();
+        let mut start = 0;
+        loop {
+            // chars that fit in `width` cells; at least one, so a character wider than the pane still moves on
+            let mut used = 0;
+            let fits = chars[start..]
+                .iter()
+                .take_while(|c| {
+                    used += c.width().unwrap_or(0);
+                    used <= width
+                })
+                .count()
+                .max(1);
+            let mut end = (start + fits).min(chars.len());
+            if end < chars.len()
+                && let Some(space) = chars[start..end].iter().rposition(|c| *c == ' ')
+                && space > 0
+            {
+                end = start + space + 1;
+            }
+            if r == row && col >= start && (col < end || end == chars.len()) {
+                // a cursor sitting exactly at the wrap width moves to the next visual line
+                let x = cells(&chars[start..col.min(chars.len())]);
+                if x >= width {
+                    cursor = (out.len() + 1, 0);
+                } else {
+                    cursor = (out.len(), x);
+                }
+            }
+            out.push(chars[start..end].iter().collect());
+            if end >= chars.len() {
+                break;
+            }
+            start = end;
+        }
+    }
+    (out, cursor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn wraps_at_spaces_and_tracks_the_cursor() {
+        let (visual, cursor) = wrap_lines(&lines("the quick brown fox"), 10, 0, 12);
+        assert_eq!(visual, vec!["the quick ", "brown fox"]);
+        assert_eq!(cursor, (1, 2));
+    }
+
+    #[test]
+    fn wide_characters_take_two_cells() {
+        let (visual, cursor) = wrap_lines(&lines("日本語のテキストです"), 8, 0, 6);
+        assert_eq!(visual, vec!["日本語の", "テキスト", "です"]);
+        // the cursor sits before "ス", two wide characters into the second line
+        assert_eq!(cursor, (1, 4));
+        for text in ["日本語 のテキスト です", "🎉🎉 party 🎉🎉🎉", "mixed 中文 text here"] {
+            for width in 2..12 {
+                let (visual, _) = wrap_lines(&lines(text), width, 0, 0);
+                for line in visual {
+                    assert!(line.width() <= width, "{:?} in {}", line, width);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,159 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    Frame,
+    layout::{Constraint, Direction, Layout},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+};
+
+use crate::keymap::{Action, Context, Dispatcher};
+use crate::storage::contacts::Contact;
+
+pub enum ContactsAction {
+    None,
+    Close,
+    Delete(String),
+    Import(String),
+    /// add "Name <email>", or replace the contact with the old address
+    Save { old: Option<String>, address: String },
+}
+
+// what the footer's input line is for
+enum Prompt {
+    Import,
+    Add,
+    /// the contact's address before the edit
+    Edit(String),
+}
+
+/// The local address book: most used first, `a` adds, `e` edits, `d` deletes, `i` imports
+/// a .vcf or mutt alias file.
+pub struct ContactsView {
+    contacts: Vec<Contact>,
+    state: ListState,
+    input: Option<(Prompt, String)>,
+    /// result of the last change or import
+    pub message: Option<String>,
+    keys: Dispatcher,
+    help: bool,
+}
+
+impl ContactsView {
+    pub fn new(mut contacts: Vec<Contact>) -> Self {
+        contacts.sort_by(|a, b| b.count.cmp(&a.count).then(b.last_used_unix.cmp(&a.last_used_unix)));
+        let mut state = ListState::default();
+        if !contacts.is_empty() {
+            state.select(Some(0));
+        }
+        ContactsView { contacts, state, input: None, message: None, keys: Dispatcher::default(), help: false }
+    }
+
+    /// Replace the list after a change, keeping the selection in range.
+    pub fn reload(&mut self, contacts: Vec<Contact>) {
+        let sel = self.state.selected().unwrap_or(0);
+        let message = self.message.take();
+        *self = ContactsView::new(contacts);
+        self.message = message;
+        if !self.contacts.is_empty() {
+            self.state.select(Some(sel.min(self.contacts.len() - 1)));
+        }
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> ContactsAction {
+        if self.help {
+            self.help = false;
+            return ContactsAction::None;
+        }
+        if let Some((_, input)) = self.input.as_mut() {
+            match key.code {
+                KeyCode::Esc => self.input = None,
+                KeyCode::Enter => {
+                    let Some((prompt, input)) = self.input.take() else { return ContactsAction::None };
+                    let text = input.trim().to_string();
+                    if text.is_empty() {
+                        return ContactsAction::None;
+                    }
+                    return match prompt {
+                        Prompt::Import => ContactsAction::Import(text),
+                        Prompt::Add => ContactsAction::Save { old: None, address: text },
+                        Prompt::Edit(old) => ContactsAction::Save { old: Some(old), address: text },
+                    };
+                }
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Char(c) => input.push(c),
+                _ => {}
+            }
+            return ContactsAction::None;
+        }
+
+        let sel = self.state.selected().unwrap_or(0);
+        match self.keys.feed(&crate::config::get().keybindings, Context::Contacts, key).first() {
+            Some(Action::Back | Action::Quit) => return ContactsAction::Close,
+            Some(Action::Help) => self.help = true,
+            Some(Action::Delete) => {
+                if let Some(c) = self.contacts.get(sel) {
+                    return ContactsAction::Delete(c.email.clone());
+                }
+            }
+            Some(Action::Import) => self.input = Some((Prompt::Import, String::new())),
+            Some(Action::Add) => self.input = Some((Prompt::Add, String::new())),
+            Some(Action::Edit) => {
+                if let Some(c) = self.contacts.get(sel) {
+                    self.input = Some((Prompt::Edit(c.email.clone()), c.address()));
+                }
+            }
+            Some(a) => super::move_selection(&mut self.state, self.contacts.len(), *a),
+            None => {}
+        }
+        ContactsAction::None
+    }
+
+    pub fn render(&mut self, frame: &mut Frame) {
+        let size = frame.size();
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(3)].as_ref())
+            .split(size);
+
+        let items: Vec<ListItem> = self
+            .contacts
+            .iter()
+            .map(|c| {
+                let alias = c.alias.as_deref().map(|a| format!(" [{}]", a)).unwrap_or_default();
+                ListItem::new(format!("{}{} — used {}×", c.address(), alias, c.count))
+            })
+            .collect();
+        let list = List::new(items)
+            .block(Block::default().title(format!("Contacts ({})", self.contacts.len())).borders(Borders::ALL))
+            .highlight_style(crate::theme::get().selection)
+            .highlight_symbol("▶ ");
+        frame.render_stateful_widget(list, chunks[0], &mut self.state);
+
+        let (title, text) = if let Some((prompt, input)) = &self.input {
+            let title = match prompt {
+                Prompt::Import => "Import vCard / mutt alias file (Enter to import, Esc to cancel)",
+                Prompt::Add => "New contact: Name <address> (Enter to add, Esc to cancel)",
+                Prompt::Edit(_) => "Edit contact: Name <address> (Enter to save, Esc to cancel)",
+            };
+            (title, input.clone())
+        } else if let Some(m) = &self.message {
+            ("Contacts", m.clone())
+        } else {
+            let hints = super::help::hints(Context::Contacts, &[
+                (Action::Add, "add"),
+                (Action::Edit, "edit"),
+                (Action::Delete, "delete"),
+                (Action::Import, "import .vcf / mutt aliases"),
+                (Action::Back, "back"),
+                (Action::Help, "help"),
+            ]);
+            ("Contacts", hints)
+        };
+        let footer = Paragraph::new(text).block(Block::default().title(title).borders(Borders::ALL));
+        frame.render_widget(footer, chunks[1]);
+        if self.help {
+            super::help::render(frame, "Contacts keys", Context::Contacts);
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use crossterm::event::KeyEvent;
+use ratatui::{
+    Frame,
+    widgets::{Block, Borders, List, ListItem, ListState},
+};
+
+use crate::gmail::GmailDraft;
+use crate::keymap::{Action, Context, Dispatcher};
+
+pub enum DraftsAction {
+    None,
+    Close,
+    Resume(GmailDraft),
+    Delete(GmailDraft),
+}
+
+/// List of saved Gmail drafts; Enter resumes one in the editor, `d` discards it.
+pub struct DraftsView {
+    drafts: Vec<GmailDraft>,
+    state: ListState,
+    error: Option<String>,
+    keys: Dispatcher,
+    help: bool,
+}
+
+impl DraftsView {
+    pub fn new(drafts: Result<Vec<GmailDraft>, String>) -> Self {
+        let (drafts, error) = match drafts {
+            Ok(d) => (d, None),
+            Err(e) => (Vec::new(), Some(e)),
+        };
+        let mut state = ListState::default();
+        if !drafts.is_empty() {
+            state.select(Some(0));
+        }
+        DraftsView { drafts, state, error, keys: Dispatcher::default(), help: false }
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> DraftsAction {
+        if self.help {
+            self.help = false;
+            return DraftsAction::None;
+        }
+        let sel = self.state.selected().unwrap_or(0);
+        match self.keys.feed(&crate::config::get().keybindings, Context::Drafts, key).first() {
+            Some(Action::Back | Action::Quit) => return DraftsAction::Close,
+            Some(Action::Help) => self.help = true,
+            Some(Action::Open) => return self.drafts.get(sel).cloned().map(DraftsAction::Resume).unwrap_or(DraftsAction::None),
+            Some(Action::Delete) => return self.drafts.get(sel).cloned().map(DraftsAction::Delete).unwrap_or(DraftsAction::None),
+            Some(a) => super::move_selection(&mut self.state, self.drafts.len(), *a),
+            None => {}
+        }
+        DraftsAction::None
+    }
+
+    pub fn render(&mut self, frame: &mut Frame) {
+        let size = frame.size();
+        let items: Vec<ListItem> = self
+            .drafts
+            .iter()
+            .map(|d| {
+                let to = d.message.to.clone().filter(|t| !t.is_empty()).unwrap_or_else(|| "(no recipient)".into());
+                let subject = d.message.subject.clone().filter(|s| !s.is_empty()).unwrap_or_else(|| "(no subject)".into());
+                ListItem::new(format!("To: {} | {}", to, subject))
+            })
+            .collect();
+
+        let title = match &self.error {
+            Some(e) => format!("Drafts (failed: {})", e),
+            None => format!(
+                "Drafts ({}) — {}",
+                self.drafts.len(),
+                super::help::hints(Context::Drafts, &[(Action::Open, "edit"), (Action::Delete, "discard"), (Action::Back, "back"), (Action::Help, "help")])
+            ),
+        };
+        let list = List::new(items)
+            .block(Block::default().title(title).borders(Borders::ALL))
+            .highlight_style(crate::theme::get().selection)
+            .highlight_symbol("▶ ");
+        frame.render_stateful_widget(list, size, &mut self.state);
+        if self.help {
+            super::help::render(frame, "Drafts keys", Context::Drafts);
+        }
+    }
+}
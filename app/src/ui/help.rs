@@ -0,0 +1,49 @@
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+
+use crate::keymap::{Action, Context};
+
+/// "enter: edit   d: discard" for a footer, with the first key each action has in the
+/// active keymap for `context`; unbound actions are left out.
+pub fn hints(context: Context, actions: &[(Action, &str)]) -> String {
+    let keymap = &crate::config::get().keybindings;
+    let parts: Vec<String> = actions
+        .iter()
+        .filter_map(|(action, label)| keymap.keys_for(*action, context).first().map(|k| format!("{}: {}", k, label)))
+        .collect();
+    parts.join("   ")
+}
+
+/// Centered overlay with the bindings of a view, from the active keymap; any key closes it.
+pub fn render(frame: &mut Frame, title: &str, context: Context) {
+    let rows = crate::config::get().keybindings.help(context);
+    let key_width = rows.iter().map(|(k, _)| k.chars().count()).max().unwrap_or(0);
+    let lines: Vec<Line> = rows
+        .iter()
+        .map(|(keys, desc)| {
+            Line::from(vec![
+                Span::styled(format!(" {:<w$}  ", keys, w = key_width), Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(*desc),
+            ])
+        })
+        .collect();
+
+    let size = frame.size();
+    let text_width = lines.iter().map(|l| l.width()).max().unwrap_or(0) as u16;
+    let width = (text_width + 3).min(size.width);
+    let height = (lines.len() as u16 + 2).min(size.height);
+    let area = Rect {
+        x: size.x + (size.width - width) / 2,
+        y: size.y + (size.height - height) / 2,
+        width,
+        height,
+    };
+    let block = Block::default().title(format!("{} — any key to close", title)).borders(Borders::ALL);
+    frame.render_widget(Clear, area);
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+}
@@ -1,6 +1,6 @@
 use std::io;
 use crossterm::{event::{self, Event, KeyCode}, terminal::{enable_raw_mode, disable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen}, execute};
-use ratatui::{backend::CrosstermBackend, Terminal, widgets::{Block, Borders, List, ListItem}, layout::{Layout, Constraint, Direction}, style::{Style, Color, Modifier}};
+use ratatui::{backend::CrosstermBackend, Terminal, widgets::{Block, Borders, List, ListItem}, layout::{Layout, Constraint, Direction}};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Provider {
@@ -16,7 +16,7 @@ pub fn prompt_provider() -> Result<Provider, io::Error> {
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let items = vec!["Google", "Outlook (not implemented)", "Skip"];
+    let items = ["Google", "Outlook (not implemented)", "Skip"];
     let mut selected: usize = 0;
 
     loop {
@@ -33,7 +33,7 @@ pub fn prompt_provider() -> Result<Provider, io::Error> {
             let list_items: Vec<ListItem> = items.iter().map(|s| ListItem::new(*s)).collect();
             let list = List::new(list_items)
                 .block(Block::default().borders(Borders::ALL))
-                .highlight_style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
+                .highlight_style(crate::theme::get().selection)
                 .highlight_symbol("▶ ");
 
             let mut state = ratatui::widgets::ListState::default();
@@ -41,23 +41,23 @@ pub fn prompt_provider() -> Result<Provider, io::Error> {
             f.render_stateful_widget(list, chunks[1], &mut state);
         })?;
 
-        if event::poll(std::time::Duration::from_millis(100))? {
-            if let Event::Key(key) = event::read()? {
-                match key.code {
-                    KeyCode::Up => {
-                        if selected == 0 {
-                            selected = items.len() - 1;
-                        } else {
-                            selected -= 1;
-                        }
+        if event::poll(std::time::Duration::from_millis(100))?
+            && let Event::Key(key) = event::read()?
+        {
+            match key.code {
+                KeyCode::Up => {
+                    if selected == 0 {
+                        selected = items.len() - 1;
+                    } else {
+                        selected -= 1;
                     }
-                    KeyCode::Down => {
-                        selected = (selected + 1) % items.len();
-                    }
-                    KeyCode::Enter => break,
-                    KeyCode::Char('q') | KeyCode::Esc => { selected = 2; break; }
-                    _ => {}
                 }
+                KeyCode::Down => {
+                    selected = (selected + 1) % items.len();
+                }
+                KeyCode::Enter => break,
+                KeyCode::Char('q') | KeyCode::Esc => { selected = 2; break; }
+                _ => {}
             }
         }
     }
@@ -1,188 +1,560 @@
+pub mod columns;
+pub mod composer;
+pub mod contacts;
+pub mod drafts;
+pub mod help;
 pub mod login;
+pub mod outbox;
+pub mod review;
+pub mod search;
 pub mod single_mail;
+pub mod status;
+pub mod thread;
 
-use crossterm::event::KeyCode;
 use ratatui::{
     Frame,
     widgets::{Block, Borders, List, ListItem, ListState},
-    style::{Style, Color, Modifier},
+    style::{Modifier, Style},
+    text::{Line, Span},
 };
 use ratatui::widgets::Paragraph;
-use ratatui::layout::Alignment;
+use ratatui::layout::{Alignment, Constraint, Direction, Layout, Rect};
 use ratatui::widgets::Wrap;
-use once_cell::sync::Lazy;
-use std::sync::Mutex;
-use crate::gmail::SimpleMail;
+use crate::events::AppEvent;
+use columns::RowData;
+use crate::keymap::Action;
+use crate::gmail::{LabelNames, SimpleMail};
 
-static MESSAGES: Lazy<Mutex<Vec<SimpleMail>>> = Lazy::new(|| Mutex::new(Vec::new()));
-
-fn sample_messages() -> Vec<(String, String, bool, String)> {
-    vec![
-        ("Alice".into(), "Meeting tomorrow".into(), false, "09:12".into()),
-        ("Bob".into(), "Rust project update".into(), true, "13:45".into()),
-        ("Charlie".into(), "Flight booking".into(), false, "Yesterday".into()),
-    ]
+// results of the active `/` search; while set, the list shows these instead of the inbox
+struct SearchResults {
+    query: String,
+    messages: Vec<SimpleMail>,
+    pending: bool,
+    error: Option<String>,
+    // inbox selection to go back to when the search is closed
+    return_to: (Option<String>, usize),
 }
 
-pub fn set_messages(msgs: Vec<SimpleMail>) {
-    let mut guard = MESSAGES.lock().unwrap();
-    *guard = msgs;
+/// Flat list of messages, or one row per conversation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ListMode {
+    #[default]
+    Messages,
+    Threads,
 }
 
-pub fn get_message(idx: usize) -> Option<SimpleMail> {
-    let guard = MESSAGES.lock().unwrap();
-    guard.get(idx).cloned()
+impl ListMode {
+    pub fn toggle(self) -> Self {
+        match self {
+            ListMode::Messages => ListMode::Threads,
+            ListMode::Threads => ListMode::Messages,
+        }
+    }
 }
 
-pub fn render_message_fullscreen(frame: &mut Frame, m: &SimpleMail) {
-    let size = frame.size();
-    let subject = m.subject.clone().unwrap_or_else(|| "(no subject)".into());
-    let from = m.from.clone().unwrap_or_else(|| "unknown".into());
-    let date = m.date.clone().unwrap_or_else(|| "".into());
-    let snippet = m.snippet.clone().unwrap_or_else(|| "".into());
+/// Where the preview pane sits next to the list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preview {
+    Right,
+    Below,
+    Off,
+}
 
-    let title = format!("{} — {}", subject, from);
-    let header = Block::default().title(title).borders(Borders::ALL);
-    let body = format!("Date: {}\n\n{}", date, snippet);
+// bounds for the preview's share of the screen, in percent
+const PREVIEW_MIN: u16 = 20;
+const PREVIEW_MAX: u16 = 80;
+const PREVIEW_STEP: u16 = 5;
 
-    let paragraph = Paragraph::new(body)
-        .block(header)
-        .wrap(Wrap { trim: true })
-        .alignment(Alignment::Left);
+#[derive(Debug, Clone)]
+pub struct ThreadSummary {
+    pub thread_id: Option<String>,
+    pub subject: Option<String>,
+    /// senders among the loaded messages
+    pub participants: Vec<String>,
+    /// messages of the thread loaded so far; older ones beyond the inbox page aren't counted
+    pub count: usize,
+    pub date: Option<String>,
+    /// some message of the thread is unread
+    pub unread: bool,
+    /// some message of the thread is starred
+    pub starred: bool,
+    /// some message of the thread has attachments
+    pub has_attachment: bool,
+    /// total of the messages' sizes, when all are known
+    pub size: Option<u64>,
+    /// labels of any of its messages
+    pub labels: Vec<String>,
+    // newest message of the thread in the list, used when the thread can't be fetched
+    pub latest: SimpleMail,
+}
 
-    frame.render_widget(paragraph, size);
+// "Alice Example <alice@example.com>" -> "Alice Example"
+fn display_name(from: &str) -> String {
+    match from.find('<') {
+        Some(i) if i > 0 => from[..i].trim().trim_matches('"').to_string(),
+        _ => from.trim().trim_matches(|c| c == '<' || c == '>').to_string(),
+    }
 }
 
-pub fn message_count() -> usize {
-    let guard = MESSAGES.lock().unwrap();
-    if guard.is_empty() {
-        sample_messages().len()
-    } else {
-        guard.len()
+// group a newest-first message list by thread id, keeping the order of each thread's newest message
+fn group_threads(msgs: &[SimpleMail]) -> Vec<ThreadSummary> {
+    let mut out: Vec<ThreadSummary> = Vec::new();
+    for m in msgs {
+        let existing = m
+            .thread_id
+            .as_ref()
+            .and_then(|tid| out.iter_mut().find(|t| t.thread_id.as_ref() == Some(tid)));
+        let name = display_name(m.from.as_deref().unwrap_or("unknown"));
+        match existing {
+            Some(t) => {
+                t.count += 1;
+                t.unread |= m.unread;
+                t.starred |= m.starred;
+                t.has_attachment |= m.has_attachment;
+                t.size = t.size.zip(m.size).map(|(a, b)| a + u64::from(b));
+                for l in &m.labels {
+                    if !t.labels.contains(l) {
+                        t.labels.push(l.clone());
+                    }
+                }
+                if !t.participants.contains(&name) {
+                    t.participants.push(name);
+                }
+            }
+            None => out.push(ThreadSummary {
+                thread_id: m.thread_id.clone(),
+                subject: m.subject.clone(),
+                participants: vec![name],
+                count: 1,
+                date: m.date.clone(),
+                unread: m.unread,
+                starred: m.starred,
+                has_attachment: m.has_attachment,
+                size: m.size.map(u64::from),
+                labels: m.labels.clone(),
+                latest: m.clone(),
+            }),
+        }
     }
+    out
 }
 
-pub fn draw(frame: &mut Frame, state: &mut ListState) {
-    let size = frame.size();
+// shown while there is no mail yet
+fn sample_messages() -> Vec<RowData> {
+    [("Alice", "Meeting tomorrow", false, false, "09:12"), ("Bob", "Rust project update", true, true, "13:45"), ("Charlie", "Flight booking", false, false, "Yesterday")]
+        .into_iter()
+        .map(|(sender, subject, read, starred, date)| RowData {
+            sender: sender.into(),
+            subject: subject.into(),
+            date: date.into(),
+            size: None,
+            attachment: false,
+            labels: Vec::new(),
+            count: 1,
+            unread: !read,
+            starred,
+        })
+        .collect()
+}
 
-    // prefer real messages when available
-    let guard = MESSAGES.lock().unwrap();
-    let raw_msgs: Vec<(String, String, bool, String)> = if guard.is_empty() {
-        sample_messages()
-    } else {
-        guard.iter().map(|m| {
-            let from = m.from.clone().unwrap_or_else(|| "unknown".into());
-            let subject = m.subject.clone().unwrap_or_else(|| "(no subject)".into());
-            let read = false; // TODO: track read state
-            let date = m.date.clone().unwrap_or_else(|| "".into());
-            (from, subject, read, date)
-        }).collect()
-    };
+// rows are keyed by message id, or by thread id in thread mode
+fn thread_key(t: &ThreadSummary) -> String {
+    t.thread_id.clone().unwrap_or_else(|| t.latest.id.clone())
+}
 
-    let preferred_bar_col: usize = 25;
-    let term_width = size.width as usize;
-    let bar_col = if term_width > preferred_bar_col + 10 {
-        preferred_bar_col
-    } else if term_width > 30 {
-        term_width.saturating_sub(20)
-    } else {
-        term_width / 2
-    };
+/// What the mailbox view shows. Owned by the UI loop; background threads send
+/// `AppEvent`s rather than touching it.
+pub struct AppState {
+    inbox: Vec<SimpleMail>,
+    // from the sync worker; messages carry label ids
+    label_names: LabelNames,
+    search: Option<SearchResults>,
+    mode: ListMode,
+    // key of the selected row, so the selection stays on the same message while the list changes
+    selected: Option<String>,
+    // where that row was, for when it disappears
+    selected_row: usize,
+    list: ListState,
+    preview: Preview,
+    // where the preview goes when toggled back on
+    preview_side: Preview,
+    preview_size: u16,
+    // rows that fit in the list at the last draw
+    page_rows: usize,
+    /// keys typed so far of an unfinished sequence
+    pub pending_keys: Option<String>,
+}
 
-    let status_col_width: usize = 8;
-    let min_subject_width: usize = 10;
-    let subject_col_width: usize = if term_width > bar_col + status_col_width + min_subject_width + 6 {
-        (term_width - bar_col - status_col_width - 6).min(40)
-    } else {
-        min_subject_width
-    };
+impl AppState {
+    pub fn new(inbox: Vec<SimpleMail>, layout: &crate::config::Layout) -> Self {
+        let mut state = AppState {
+            inbox,
+            label_names: LabelNames::new(),
+            search: None,
+            mode: layout.list_mode,
+            selected: None,
+            selected_row: 0,
+            list: ListState::default(),
+            preview: layout.preview,
+            preview_side: if layout.preview == Preview::Off { Preview::Right } else { layout.preview },
+            preview_size: layout.preview_size.clamp(PREVIEW_MIN, PREVIEW_MAX),
+            page_rows: 1,
+            pending_keys: None,
+        };
+        state.select_row(0);
+        state
+    }
 
-    let mut items: Vec<ListItem> = Vec::new();
+    /// Apply an update from a worker. Returns false when nothing visible changed.
+    pub fn apply(&mut self, event: AppEvent) -> bool {
+        match event {
+            AppEvent::Inbox { messages, labels } => {
+                if messages == self.inbox && labels == self.label_names {
+                    return false;
+                }
+                self.inbox = messages;
+                self.label_names = labels;
+            }
+            // results for a query the user already left (or replaced) are dropped
+            AppEvent::SearchResults { query, result } => {
+                let Some(search) = self.search.as_mut().filter(|s| s.query == query) else {
+                    return false;
+                };
+                search.pending = false;
+                match result {
+                    Ok(msgs) => search.messages = msgs,
+                    Err(e) => search.error = Some(e),
+                }
+            }
+            AppEvent::Redraw => return true,
+        }
+        self.reselect();
+        true
+    }
 
-    for (from, subject, read, sent) in &raw_msgs {
-        let dot = if *read { "○" } else { "●" };
-        let mut left = format!("{} From: {}", dot, from);
+    // search results while a search is active, otherwise the inbox
+    fn messages(&self) -> &[SimpleMail] {
+        match &self.search {
+            Some(search) => &search.messages,
+            None => &self.inbox,
+        }
+    }
 
-        if left.chars().count() >= bar_col {
-            left = left.chars().take(bar_col.saturating_sub(1)).collect();
-            left.push('…');
-        } else {
-            let pad = bar_col.saturating_sub(left.chars().count());
-            left.push_str(&" ".repeat(pad));
+    fn row_keys(&self) -> Vec<String> {
+        match self.mode {
+            ListMode::Messages => self.messages().iter().map(|m| m.id.clone()).collect(),
+            ListMode::Threads => group_threads(self.messages()).iter().map(thread_key).collect(),
         }
+    }
 
-        let mut subj = subject.to_string();
-        if subj.chars().count() >= subject_col_width {
-            subj = subj.chars().take(subject_col_width.saturating_sub(1)).collect();
-            subj.push('…');
-        } else {
-            let pad = subject_col_width.saturating_sub(subj.chars().count());
-            subj.push_str(&" ".repeat(pad));
+    fn select_row(&mut self, row: usize) {
+        let keys = self.row_keys();
+        self.selected_row = row.min(keys.len().saturating_sub(1));
+        self.selected = keys.into_iter().nth(self.selected_row);
+    }
+
+    // find the selected row again after the list changed; if its message is gone, stay at the same position
+    fn reselect(&mut self) {
+        let keys = self.row_keys();
+        match self.selected.as_ref().and_then(|k| keys.iter().position(|x| x == k)) {
+            Some(row) => self.selected_row = row,
+            None => self.select_row(self.selected_row),
         }
+    }
 
-        let status = if *read { "Read" } else { "Unread" };
-        let mut status_field = status.to_string();
-        if status_field.chars().count() >= status_col_width {
-            status_field = status_field.chars().take(status_col_width.saturating_sub(1)).collect();
-            status_field.push('…');
-        } else {
-            let pad = status_col_width.saturating_sub(status_field.chars().count());
-            status_field.push_str(&" ".repeat(pad));
+    pub fn mode(&self) -> ListMode {
+        self.mode
+    }
+
+    /// Switch between messages and threads, keeping the selected message (or its thread) selected.
+    pub fn toggle_mode(&mut self) {
+        let current = self.selected_mail();
+        self.mode = self.mode.toggle();
+        self.selected = current.map(|m| match self.mode {
+            ListMode::Messages => m.id,
+            ListMode::Threads => m.thread_id.unwrap_or(m.id),
+        });
+        self.reselect();
+    }
+
+    pub fn selected_mail(&self) -> Option<SimpleMail> {
+        match self.mode {
+            ListMode::Messages => self.messages().get(self.selected_row).cloned(),
+            ListMode::Threads => self.selected_thread().map(|t| t.latest),
         }
+    }
 
-        let line = format!("{} | {} | {} | {}", left, subj, status_field, sent);
-        items.push(ListItem::new(line));
+    pub fn selected_thread(&self) -> Option<ThreadSummary> {
+        group_threads(self.messages()).into_iter().nth(self.selected_row)
+    }
 
-        let sep = if term_width > 0 {
-            "─".repeat(term_width)
-        } else {
-            String::from("─")
+    pub fn begin_search(&mut self, query: &str) {
+        let return_to = match self.search.take() {
+            Some(previous) => previous.return_to,
+            None => (self.selected.clone(), self.selected_row),
         };
-        items.push(ListItem::new(sep));
+        self.search = Some(SearchResults {
+            query: query.to_string(),
+            messages: Vec::new(),
+            pending: true,
+            error: None,
+            return_to,
+        });
+        self.select_row(0);
     }
 
-    // selection: default to first
-    if state.selected().is_none() && !raw_msgs.is_empty() {
-        state.select(Some(0));
+    /// Back to the mailbox, where the selection was before searching.
+    pub fn clear_search(&mut self) {
+        if let Some(search) = self.search.take() {
+            (self.selected, self.selected_row) = search.return_to;
+            self.reselect();
+        }
     }
 
-    let list = List::new(items)
-        .block(Block::default().title("Inbox").borders(Borders::ALL))
-        .highlight_style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
-        .highlight_symbol("");
+    pub fn toggle_preview(&mut self) {
+        self.preview = if self.preview == Preview::Off { self.preview_side } else { Preview::Off };
+    }
 
-    frame.render_stateful_widget(list, size, state);
-}
-// move selection to next message (each message uses 2 ListItems)
-pub fn select_next(state: &mut ListState, msg_count: usize) {
-    if msg_count == 0 {
-        state.select(None);
-        return;
+    /// Grow (or, with a negative step count, shrink) the preview pane. Returns true when its size changed.
+    pub fn resize_preview(&mut self, steps: i16) -> bool {
+        if self.preview == Preview::Off {
+            return false;
+        }
+        let before = self.preview_size;
+        let size = self.preview_size as i16 + steps * PREVIEW_STEP as i16;
+        self.preview_size = (size.max(0) as u16).clamp(PREVIEW_MIN, PREVIEW_MAX);
+        self.preview_size != before
     }
-    let current_msg_idx = state.selected().map(|i| i / 2).unwrap_or(0);
-    let next = if current_msg_idx + 1 >= msg_count {
-        current_msg_idx
-    } else {
-        current_msg_idx + 1
+
+    pub fn search_active(&self) -> bool {
+        self.search.is_some()
+    }
+
+    /// Move the selection down a row. Returns true when it moved.
+    pub fn select_next(&mut self) -> bool {
+        let before = self.selected_row;
+        self.select_row(self.selected_row + 1);
+        self.selected_row != before
+    }
+
+    pub fn select_prev(&mut self) -> bool {
+        let before = self.selected_row;
+        self.select_row(self.selected_row.saturating_sub(1));
+        self.selected_row != before
+    }
+
+    /// Move the selection for a motion action (up, down, page up/down, top, bottom).
+    /// Returns true when it moved.
+    pub fn move_selection(&mut self, action: Action) -> bool {
+        let before = self.selected_row;
+        let row = match action {
+            Action::Up => before.saturating_sub(1),
+            Action::Down => before + 1,
+            Action::PageUp => before.saturating_sub(self.page_rows),
+            Action::PageDown => before + self.page_rows,
+            Action::Top => 0,
+            Action::Bottom => usize::MAX,
+            _ => before,
+        };
+        self.select_row(row);
+        self.selected_row != before
+    }
+}
+
+// rows skipped by page up / page down in the smaller list views
+const LIST_PAGE: usize = 10;
+
+/// Move the selection of a list view (drafts, contacts, ...) of `len` rows for a motion
+/// action; other actions leave it alone.
+pub fn move_selection(state: &mut ListState, len: usize, action: Action) {
+    let Some(last) = len.checked_sub(1) else { return };
+    let sel = state.selected().unwrap_or(0);
+    let next = match action {
+        Action::Up => sel.saturating_sub(1),
+        Action::Down => sel + 1,
+        Action::PageUp => sel.saturating_sub(LIST_PAGE),
+        Action::PageDown => sel + LIST_PAGE,
+        Action::Top => 0,
+        Action::Bottom => last,
+        _ => sel,
     };
-    state.select(Some(next * 2));
+    state.select(Some(next.min(last)));
 }
 
-pub fn select_prev(state: &mut ListState, msg_count: usize) {
-    if msg_count == 0 {
-        state.select(None);
-        return;
+const LINK_SCHEMES: &[&str] = &["https://", "http://", "mailto:"];
+
+// char ranges of the links in a line; trailing punctuation is left out
+fn link_ranges(chars: &[char]) -> Vec<(usize, usize)> {
+    let lower: Vec<char> = chars.iter().map(|c| c.to_ascii_lowercase()).collect();
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let starts = (i == 0 || !chars[i - 1].is_alphanumeric())
+            && LINK_SCHEMES.iter().any(|s| lower[i..].iter().take(s.len()).copied().eq(s.chars()));
+        if !starts {
+            i += 1;
+            continue;
+        }
+        let mut end = i + chars[i..].iter().take_while(|c| !c.is_whitespace() && !"<>\"'".contains(**c)).count();
+        while end > i && ".,;:!?)]".contains(chars[end - 1]) {
+            end -= 1;
+        }
+        out.push((i, end));
+        i = end.max(i + 1);
     }
-    let current_msg_idx = state.selected().map(|i| i / 2).unwrap_or(0);
-    let prev = if current_msg_idx == 0 { 0 } else { current_msg_idx - 1 };
-    state.select(Some(prev * 2));
+    out
 }
 
-// handle arrow keys; call from your event loop with the KeyCode and number of messages
-pub fn handle_key(state: &mut ListState, key: KeyCode, msg_count: usize) {
-    match key {
-        KeyCode::Up => select_prev(state, msg_count),
-        KeyCode::Down => select_next(state, msg_count),
-        _ => {}
+pub(crate) fn is_quote(line: &str) -> bool {
+    line.trim_start().starts_with('>')
+}
+
+/// A style per char of a message line: `base` for all of it, links on top.
+pub(crate) fn text_styles(chars: &[char], base: Style) -> Vec<Style> {
+    let mut styles = vec![base; chars.len()];
+    let link = crate::theme::get().link;
+    for (start, end) in link_ranges(chars) {
+        styles[start..end].iter_mut().for_each(|s| *s = s.patch(link));
     }
+    styles
+}
+
+/// Runs of equally styled chars as spans.
+pub(crate) fn styled_spans(chars: &[char], styles: &[Style]) -> Line<'static> {
+    let mut spans = Vec::new();
+    let mut start = 0;
+    for i in 1..=chars.len() {
+        if i == chars.len() || styles[i] != styles[start] {
+            spans.push(Span::styled(chars[start..i].iter().collect::<String>(), styles[start]));
+            start = i;
+        }
+    }
+    Line::from(spans)
+}
+
+// a body line with its quote and link styles
+fn body_line(line: &str) -> Line<'static> {
+    let chars: Vec<char> = line.chars().collect();
+    let base = if is_quote(line) { crate::theme::get().quote } else { Style::default() };
+    styled_spans(&chars, &text_styles(&chars, base))
+}
+
+fn message_view(m: &SimpleMail) -> Paragraph<'static> {
+    let theme = crate::theme::get();
+    let subject = m.subject.clone().unwrap_or_else(|| "(no subject)".into());
+    let from = m.from.clone().unwrap_or_else(|| "unknown".into());
+    let date = m.date.clone().unwrap_or_else(|| "".into());
+    let text = m.body.clone().or_else(|| m.snippet.clone()).unwrap_or_else(|| "".into());
+
+    let title = format!("{} — {}", subject, from);
+    let header = Block::default().title(title).borders(Borders::ALL);
+    let mut lines = vec![Line::styled(format!("Date: {}", date), theme.header), Line::from("")];
+    lines.extend(text.replace('\r', "").lines().map(body_line));
+
+    Paragraph::new(lines)
+        .block(header)
+        .wrap(Wrap { trim: true })
+        .alignment(Alignment::Left)
+}
+
+pub fn draw(frame: &mut Frame, state: &mut AppState) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(1)].as_ref())
+        .split(frame.size());
+    let status_area = chunks[1];
+    // the list, and beside or below it the selected message
+    let (size, preview_area) = match state.preview {
+        Preview::Off => (chunks[0], None),
+        side => {
+            let direction = if side == Preview::Right { Direction::Horizontal } else { Direction::Vertical };
+            let panes = Layout::default()
+                .direction(direction)
+                .constraints([Constraint::Percentage(100 - state.preview_size), Constraint::Percentage(state.preview_size)].as_ref())
+                .split(chunks[0]);
+            (panes[0], Some(panes[1]))
+        }
+    };
+    if let Some(area) = preview_area {
+        match state.selected_mail() {
+            Some(m) => frame.render_widget(message_view(&m), area),
+            None => frame.render_widget(Block::default().title("Preview").borders(Borders::ALL), area),
+        }
+    }
+
+    // search results replace the mailbox while a search is active
+    let mode = state.mode;
+    let searching = state.search.is_some();
+    let msgs = state.messages();
+    let (title, mailbox) = if let Some(s) = state.search.as_ref() {
+        let title = if s.pending {
+            format!("Search: {} (searching…)", s.query)
+        } else if let Some(e) = &s.error {
+            format!("Search: {} (failed: {})", s.query, e)
+        } else {
+            format!("Search: {} ({} results)", s.query, s.messages.len())
+        };
+        (title, format!("Search: {}", s.query))
+    } else {
+        let title = match crate::config::get().account() {
+            Some(a) => format!("Inbox — {}", a.name),
+            None => "Inbox".to_string(),
+        };
+        (title, "Inbox".to_string())
+    };
+
+    let counts = match mode {
+        ListMode::Messages => format!("{} messages", msgs.len()),
+        // only what is loaded: the threads may have older messages beyond the page
+        ListMode::Threads => format!("{} threads in {} loaded messages", group_threads(msgs).len(), msgs.len()),
+    };
+    status::render(frame, status_area, &mailbox, &counts, state.pending_keys.as_deref());
+
+    let rows: Vec<RowData> = match mode {
+        // prefer real messages when available
+        _ if msgs.is_empty() && !searching => sample_messages(),
+        ListMode::Messages => msgs.iter().map(|m| RowData::message(m, &state.label_names)).collect(),
+        ListMode::Threads => group_threads(msgs).iter().map(|t| RowData::thread(t, &state.label_names)).collect(),
+    };
+    let placeholder = msgs.is_empty();
+    let title = match mode {
+        ListMode::Messages => title,
+        ListMode::Threads => format!("{} — threads", title),
+    };
+
+    let block = Block::default().title(title).borders(Borders::ALL);
+    let inner = block.inner(size);
+    frame.render_widget(block, size);
+    let columns = columns::visible(&crate::config::get().layout.columns, mode);
+    let widths = columns::widths(&columns, inner.width as usize);
+    let (header_area, list_area) = match inner.height {
+        0 => (None, inner),
+        _ => (Some(Rect { height: 1, ..inner }), Rect { y: inner.y + 1, height: inner.height - 1, ..inner }),
+    };
+    state.page_rows = (list_area.height as usize).max(1);
+    if let Some(area) = header_area {
+        let header = Paragraph::new(columns::header(&columns, &widths)).style(Style::default().add_modifier(Modifier::BOLD));
+        frame.render_widget(header, area);
+    }
+
+    let theme = crate::theme::get();
+    let items: Vec<ListItem> = rows
+        .iter()
+        .map(|row| {
+            let mut style = Style::default();
+            if row.unread {
+                style = style.patch(theme.unread);
+            }
+            if row.starred {
+                style = style.patch(theme.flagged);
+            }
+            ListItem::new(row.line(&columns, &widths)).style(style)
+        })
+        .collect();
+
+    let row = if placeholder { 0 } else { state.selected_row };
+    state.list.select((!rows.is_empty()).then_some(row));
+
+    let list = List::new(items).highlight_style(theme.selection);
+    frame.render_stateful_widget(list, list_area, &mut state.list);
 }
@@ -0,0 +1,122 @@
+use crossterm::event::KeyEvent;
+use ratatui::{
+    Frame,
+    layout::{Constraint, Direction, Layout},
+    style::Style,
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+};
+
+use crate::keymap::{Action, Context, Dispatcher};
+use crate::storage::outbox::{Entry, Status, now_unix};
+
+pub enum OutboxAction {
+    None,
+    Close,
+    Edit(String),
+    Retry(String),
+    Delete(String),
+}
+
+/// Queued messages with their send state; Enter edits, `r` retries now, `d` deletes.
+pub struct OutboxView {
+    entries: Vec<Entry>,
+    state: ListState,
+    /// result of the last action
+    pub message: Option<String>,
+    keys: Dispatcher,
+    help: bool,
+}
+
+impl OutboxView {
+    pub fn new(entries: Vec<Entry>) -> Self {
+        let mut state = ListState::default();
+        if !entries.is_empty() {
+            state.select(Some(0));
+        }
+        OutboxView { entries, state, message: None, keys: Dispatcher::default(), help: false }
+    }
+
+    /// Replace the list (the worker changes it underneath), keeping the selection in range.
+    pub fn reload(&mut self, entries: Vec<Entry>) {
+        let sel = self.state.selected().unwrap_or(0);
+        self.entries = entries;
+        self.state.select((!self.entries.is_empty()).then(|| sel.min(self.entries.len() - 1)));
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> OutboxAction {
+        if self.help {
+            self.help = false;
+            return OutboxAction::None;
+        }
+        let sel = self.state.selected().unwrap_or(0);
+        let id = self.entries.get(sel).map(|e| e.id.clone());
+        match self.keys.feed(&crate::config::get().keybindings, Context::Outbox, key).first() {
+            Some(Action::Back | Action::Quit) => return OutboxAction::Close,
+            Some(Action::Help) => self.help = true,
+            Some(Action::Open | Action::Edit) => return id.map(OutboxAction::Edit).unwrap_or(OutboxAction::None),
+            Some(Action::Retry) => return id.map(OutboxAction::Retry).unwrap_or(OutboxAction::None),
+            Some(Action::Delete) => return id.map(OutboxAction::Delete).unwrap_or(OutboxAction::None),
+            Some(a) => super::move_selection(&mut self.state, self.entries.len(), *a),
+            None => {}
+        }
+        OutboxAction::None
+    }
+
+    pub fn render(&mut self, frame: &mut Frame) {
+        let size = frame.size();
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(3)].as_ref())
+            .split(size);
+
+        let now = now_unix();
+        let items: Vec<ListItem> = self
+            .entries
+            .iter()
+            .map(|e| {
+                let state = match e.status {
+                    Status::Sending => "sending".to_string(),
+                    Status::Failed => "failed".to_string(),
+                    Status::Pending if e.next_attempt_unix > now => {
+                        format!("retry in {}s", e.next_attempt_unix - now)
+                    }
+                    Status::Pending => "queued".to_string(),
+                };
+                let to = if e.draft.to.is_empty() { "(no recipient)" } else { &e.draft.to };
+                let subject = if e.draft.subject.is_empty() { "(no subject)" } else { &e.draft.subject };
+                let mut line = format!("[{}] To: {} | {}", state, to, subject);
+                if let Some(err) = &e.last_error {
+                    line.push_str(&format!(" — attempt {}: {}", e.attempts, err));
+                }
+                let style = match e.status {
+                    Status::Failed => crate::theme::get().error,
+                    _ => Style::default(),
+                };
+                ListItem::new(line).style(style)
+            })
+            .collect();
+        let list = List::new(items)
+            .block(Block::default().title(format!("Outbox ({})", self.entries.len())).borders(Borders::ALL))
+            .highlight_style(crate::theme::get().selection)
+            .highlight_symbol("▶ ");
+        frame.render_stateful_widget(list, chunks[0], &mut self.state);
+
+        let text = self
+            .message
+            .clone()
+            .unwrap_or_else(|| {
+                super::help::hints(Context::Outbox, &[
+                    (Action::Open, "edit"),
+                    (Action::Retry, "retry now"),
+                    (Action::Delete, "delete"),
+                    (Action::Back, "back"),
+                    (Action::Help, "help"),
+                ])
+            });
+        let footer = Paragraph::new(text).block(Block::default().title("Outbox").borders(Borders::ALL));
+        frame.render_widget(footer, chunks[1]);
+        if self.help {
+            super::help::render(frame, "Outbox keys", Context::Outbox);
+        }
+    }
+}
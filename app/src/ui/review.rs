@@ -0,0 +1,150 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    Frame,
+    layout::{Constraint, Direction, Layout},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, Wrap},
+};
+
+use crate::compose::Draft;
+use crate::keymap::{Action, Context, Dispatcher};
+
+pub enum ReviewAction {
+    None,
+    Send,
+    Edit,
+    SaveDraft,
+    Discard,
+    Attach(String),
+    /// cycle the From identity
+    NextIdentity,
+}
+
+/// Shown after the editor closes: the message as it will be sent, plus what to do with it.
+pub struct ReviewView {
+    /// last send/save failure, shown until the next action
+    pub error: Option<String>,
+    // path being typed after `attach`
+    attach_input: Option<String>,
+    // `discard` waits for a "y"
+    confirm_discard: bool,
+    keys: Dispatcher,
+    help: bool,
+}
+
+impl ReviewView {
+    pub fn new() -> Self {
+        ReviewView { error: None, attach_input: None, confirm_discard: false, keys: Dispatcher::default(), help: false }
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> ReviewAction {
+        if self.help {
+            self.help = false;
+            return ReviewAction::None;
+        }
+        if let Some(input) = self.attach_input.as_mut() {
+            match key.code {
+                KeyCode::Esc => self.attach_input = None,
+                KeyCode::Enter => {
+                    let path = input.trim().to_string();
+                    self.attach_input = None;
+                    if !path.is_empty() {
+                        return ReviewAction::Attach(path);
+                    }
+                }
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Char(c) => input.push(c),
+                _ => {}
+            }
+            return ReviewAction::None;
+        }
+
+        if self.confirm_discard {
+            self.confirm_discard = false;
+            return if matches!(key.code, KeyCode::Char('y' | 'Y')) { ReviewAction::Discard } else { ReviewAction::None };
+        }
+
+        match self.keys.feed(&crate::config::get().keybindings, Context::Review, key).first() {
+            Some(Action::Send) => return ReviewAction::Send,
+            Some(Action::Edit) => return ReviewAction::Edit,
+            Some(Action::SaveDraft) => return ReviewAction::SaveDraft,
+            Some(Action::ChangeFrom) => return ReviewAction::NextIdentity,
+            Some(Action::Discard) => self.confirm_discard = true,
+            Some(Action::Attach) => self.attach_input = Some(String::new()),
+            Some(Action::Help) => self.help = true,
+            _ => {}
+        }
+        ReviewAction::None
+    }
+
+    pub fn render(&self, frame: &mut Frame, draft: &Draft) {
+        let size = frame.size();
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(3)].as_ref())
+            .split(size);
+
+        let label = Style::default().add_modifier(Modifier::BOLD);
+        let field = |name: &'static str, value: &str| {
+            Line::from(vec![Span::styled(format!("{:<9}", name), label), Span::raw(value.to_string())])
+        };
+
+        let mut lines = vec![
+            field("From:", if draft.from.is_empty() { "(account default)" } else { &draft.from }),
+            field("To:", &draft.to),
+        ];
+        if !draft.cc.is_empty() {
+            lines.push(field("Cc:", &draft.cc));
+        }
+        if !draft.bcc.is_empty() {
+            lines.push(field("Bcc:", &draft.bcc));
+        }
+        lines.push(field("Subject:", &draft.subject));
+        for a in &draft.attachments {
+            let size = std::fs::metadata(crate::compose::expand_home(a))
+                .map(|m| format!("{} KiB", m.len().div_ceil(1024)))
+                .unwrap_or_else(|_| "missing".into());
+            lines.push(field("Attach:", &format!("{} ({})", a, size)));
+        }
+        lines.push(Line::from(""));
+        lines.extend(draft.full_body().lines().map(|l| Line::from(l.to_string())));
+
+        let preview = Paragraph::new(lines)
+            .block(Block::default().title("Review message").borders(Borders::ALL))
+            .wrap(Wrap { trim: false });
+        frame.render_widget(preview, chunks[0]);
+
+        let (title, text, style) = if let Some(input) = &self.attach_input {
+            ("Attach file (Enter to add, Esc to cancel)", input.clone(), Style::default())
+        } else if self.confirm_discard {
+            ("Discard", "Discard this message? y: discard, any other key: keep it".to_string(), crate::theme::get().error)
+        } else if let Some(e) = &self.error {
+            ("Error", e.clone(), crate::theme::get().error)
+        } else {
+            let hints = super::help::hints(
+                Context::Review,
+                &[
+                    (Action::Send, "send"),
+                    (Action::Edit, "edit again"),
+                    (Action::ChangeFrom, "change From"),
+                    (Action::Attach, "attach file"),
+                    (Action::SaveDraft, "save draft"),
+                    (Action::Discard, "discard"),
+                    (Action::Help, "help"),
+                ],
+            );
+            ("Actions", hints, Style::default())
+        };
+        let footer = Paragraph::new(text)
+            .style(style)
+            .block(Block::default().title(title).borders(Borders::ALL));
+        frame.render_widget(footer, chunks[1]);
+
+        if self.help {
+            super::help::render(frame, "Review keys", Context::Review);
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use crossterm::event::KeyCode;
+use ratatui::{
+    Frame,
+    layout::Rect,
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+
+pub enum PromptOutcome {
+    Continue,
+    Submit(String),
+    Cancel,
+}
+
+/// Single-line `/` prompt for Gmail search queries, with Up/Down walking the saved history.
+pub struct SearchPrompt {
+    input: String,
+    history: Vec<String>,
+    history_pos: Option<usize>,
+}
+
+impl SearchPrompt {
+    pub fn new(history: Vec<String>) -> Self {
+        SearchPrompt { input: String::new(), history, history_pos: None }
+    }
+
+    pub fn handle_key(&mut self, key: KeyCode) -> PromptOutcome {
+        match key {
+            KeyCode::Esc => return PromptOutcome::Cancel,
+            KeyCode::Enter => {
+                let q = self.input.trim().to_string();
+                return if q.is_empty() { PromptOutcome::Cancel } else { PromptOutcome::Submit(q) };
+            }
+            KeyCode::Backspace => {
+                self.input.pop();
+                self.history_pos = None;
+            }
+            KeyCode::Char(c) => {
+                self.input.push(c);
+                self.history_pos = None;
+            }
+            KeyCode::Up => {
+                let next = self.history_pos.map(|i| i + 1).unwrap_or(0);
+                if let Some(h) = self.history.get(next) {
+                    self.input = h.clone();
+                    self.history_pos = Some(next);
+                }
+            }
+            KeyCode::Down => match self.history_pos {
+                Some(0) | None => {
+                    self.input.clear();
+                    self.history_pos = None;
+                }
+                Some(i) => {
+                    self.input = self.history[i - 1].clone();
+                    self.history_pos = Some(i - 1);
+                }
+            },
+            _ => {}
+        }
+        PromptOutcome::Continue
+    }
+
+    pub fn render(&self, frame: &mut Frame) {
+        let size = frame.size();
+        if size.height < 3 {
+            return;
+        }
+        let area = Rect { x: size.x, y: size.y + size.height - 3, width: size.width, height: 3 };
+        let prompt = Paragraph::new(format!("/{}", self.input))
+            .block(Block::default().title("Search (Gmail query, ↑/↓ history)").borders(Borders::ALL));
+        frame.render_widget(Clear, area);
+        frame.render_widget(prompt, area);
+        let cursor_x = area.x + 2 + self.input.chars().count() as u16;
+        frame.set_cursor(cursor_x.min(area.x + area.width.saturating_sub(2)), area.y + 1);
+    }
+}
@@ -1,3 +1,331 @@
-// small helper module kept for single-message rendering (if needed)
-// currently the main UI renders message fullscreen directly from `ui::render_message_fullscreen`
-// This file reserved for future single-message rendering helpers.
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    Frame,
+    layout::{Constraint, Direction, Layout},
+    style::{Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, Paragraph},
+};
+use unicode_width::UnicodeWidthChar;
+
+use crate::gmail::SimpleMail;
+use crate::keymap::{Action, Context, Dispatcher};
+
+pub enum ReaderAction {
+    None,
+    Close,
+    /// open the next (older) message in the list
+    Next,
+    /// open the previous (newer) message in the list
+    Prev,
+}
+
+// a match: wrapped line, then start and end in chars
+type Match = (usize, usize, usize);
+
+/// Full-screen reader for one message: scrolling, `/` search with n/N, and n/p to move
+/// through the list while no search is active. Keys come from the keymap's reader bindings.
+pub struct ReaderView {
+    mail: SimpleMail,
+    // header and body lines before wrapping
+    source: Vec<String>,
+    // source lines that are headers, the rest is the body
+    header_rows: usize,
+    lines: Vec<String>,
+    // source line of each wrapped line
+    origin: Vec<usize>,
+    wrapped_for: u16,
+    scroll: usize,
+    // body rows shown at the last render, for paging
+    height: usize,
+    /// text typed after `/`, while the prompt is open
+    prompt: Option<String>,
+    query: Option<String>,
+    matches: Vec<Match>,
+    current: usize,
+    keys: Dispatcher,
+    help: bool,
+}
+
+// wrap at word boundaries where possible, by display width so wide (CJK, emoji) characters
+// don't run past the edge; also returns the source line of each
+fn wrap(source: &[String], width: usize) -> (Vec<String>, Vec<usize>) {
+    let width = width.max(1);
+    let mut out = Vec::new();
+    let mut origin = Vec::new();
+    for (n, line) in source.iter().enumerate() {
+        let mut rest: Vec<char> = line.chars().collect();
+        loop {
+            // chars that fit in `width` cells; at least one, so a character wider than the pane still moves on
+            let mut used = 0;
+            let fits = rest
+                .iter()
+                .take_while(|c| {
+                    used += c.width().unwrap_or(0);
+                    used <= width
+                })
+                .count()
+                .max(1);
+            if fits >= rest.len() {
+                break;
+            }
+            // a space right after the last char that fits is a fine place to break too
+            let cut = rest[..=fits].iter().rposition(|c| *c == ' ').filter(|i| *i > 0).unwrap_or(fits);
+            out.push(rest[..cut].iter().collect());
+            origin.push(n);
+            let skip = if rest[cut] == ' ' { cut + 1 } else { cut };
+            rest.drain(..skip);
+        }
+        out.push(rest.into_iter().collect());
+        origin.push(n);
+    }
+    (out, origin)
+}
+
+fn fold(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+// case-insensitive, non-overlapping
+fn find_matches(lines: &[String], query: &str) -> Vec<Match> {
+    let needle: Vec<char> = query.chars().map(fold).collect();
+    let mut out = Vec::new();
+    if needle.is_empty() {
+        return out;
+    }
+    for (n, line) in lines.iter().enumerate() {
+        let hay: Vec<char> = line.chars().map(fold).collect();
+        let mut i = 0;
+        while i + needle.len() <= hay.len() {
+            if hay[i..i + needle.len()] == needle[..] {
+                out.push((n, i, i + needle.len()));
+                i += needle.len();
+            } else {
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+impl ReaderView {
+    pub fn new(mail: SimpleMail) -> Self {
+        let mut source = Vec::new();
+        for (name, value) in [("From", &mail.from), ("To", &mail.to), ("Cc", &mail.cc), ("Date", &mail.date), ("Subject", &mail.subject)] {
+            if let Some(v) = value.as_deref().filter(|v| !v.is_empty()) {
+                source.push(format!("{}: {}", name, v));
+            }
+        }
+        let header_rows = source.len();
+        source.push(String::new());
+        let text = mail.body.clone().or_else(|| mail.snippet.clone()).unwrap_or_default();
+        source.extend(text.replace('\r', "").replace('\t', "    ").lines().map(str::to_string));
+
+        ReaderView {
+            mail,
+            source,
+            header_rows,
+            lines: Vec::new(),
+            origin: Vec::new(),
+            wrapped_for: 0,
+            scroll: 0,
+            height: 1,
+            prompt: None,
+            query: None,
+            matches: Vec::new(),
+            current: 0,
+            keys: Dispatcher::default(),
+            help: false,
+        }
+    }
+
+    fn max_scroll(&self) -> usize {
+        self.lines.len().saturating_sub(self.height)
+    }
+
+    // bring the current match into view, a third of the way down
+    fn show_current(&mut self) {
+        if let Some((line, _, _)) = self.matches.get(self.current) {
+            if *line < self.scroll || *line >= self.scroll + self.height {
+                self.scroll = line.saturating_sub(self.height / 3);
+            }
+            self.scroll = self.scroll.min(self.max_scroll());
+        }
+    }
+
+    fn search(&mut self) {
+        self.matches = self.query.as_deref().map(|q| find_matches(&self.lines, q)).unwrap_or_default();
+        // start from the first match on screen or below
+        self.current = self.matches.iter().position(|m| m.0 >= self.scroll).unwrap_or(0);
+        self.show_current();
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> ReaderAction {
+        if self.help {
+            self.help = false;
+            return ReaderAction::None;
+        }
+        if let Some(prompt) = self.prompt.as_mut() {
+            match key.code {
+                KeyCode::Esc => self.prompt = None,
+                KeyCode::Enter => {
+                    let q = self.prompt.take().unwrap_or_default();
+                    self.query = Some(q).filter(|q| !q.is_empty());
+                    self.search();
+                }
+                KeyCode::Backspace => {
+                    prompt.pop();
+                }
+                KeyCode::Char(c) => prompt.push(c),
+                _ => {}
+            }
+            return ReaderAction::None;
+        }
+
+        let page = self.height.saturating_sub(1).max(1);
+        let searching = !self.matches.is_empty();
+        let actions = self.keys.feed(&crate::config::get().keybindings, Context::Reader, key);
+        // a key bound to several actions (n: next match, else next message) does the first that applies
+        let action = actions.into_iter().find(|a| searching || !matches!(a, Action::NextMatch | Action::PrevMatch));
+        match action {
+            Some(Action::Back) if self.query.is_some() => {
+                self.query = None;
+                self.matches.clear();
+            }
+            Some(Action::Back | Action::Quit) => return ReaderAction::Close,
+            Some(Action::Help) => self.help = true,
+            Some(Action::Up) => self.scroll = self.scroll.saturating_sub(1),
+            Some(Action::Down) => self.scroll = (self.scroll + 1).min(self.max_scroll()),
+            Some(Action::PageUp) => self.scroll = self.scroll.saturating_sub(page),
+            Some(Action::PageDown) => self.scroll = (self.scroll + page).min(self.max_scroll()),
+            Some(Action::Top) => self.scroll = 0,
+            Some(Action::Bottom) => self.scroll = self.max_scroll(),
+            Some(Action::Search) => self.prompt = Some(String::new()),
+            Some(Action::NextMatch) => {
+                self.current = (self.current + 1) % self.matches.len();
+                self.show_current();
+            }
+            Some(Action::PrevMatch) => {
+                self.current = (self.current + self.matches.len() - 1) % self.matches.len();
+                self.show_current();
+            }
+            Some(Action::NextMessage) => return ReaderAction::Next,
+            Some(Action::PrevMessage) => return ReaderAction::Prev,
+            _ => {}
+        }
+        ReaderAction::None
+    }
+
+    // the line in its header, quote and link styles, with its matches highlighted; the current one stands out
+    fn styled_line(&self, n: usize) -> Line<'static> {
+        let theme = crate::theme::get();
+        let chars: Vec<char> = self.lines[n].chars().collect();
+        let source = self.origin[n];
+        let base = if source < self.header_rows {
+            theme.header
+        } else if super::is_quote(&self.source[source]) {
+            theme.quote
+        } else {
+            Style::default()
+        };
+        let mut styles = super::text_styles(&chars, base);
+        for (i, &(_, start, end)) in self.matches.iter().enumerate().filter(|(_, m)| m.0 == n) {
+            let style = if i == self.current {
+                theme.accent.add_modifier(Modifier::REVERSED | Modifier::BOLD)
+            } else {
+                Style::default().add_modifier(Modifier::REVERSED)
+            };
+            styles[start..end].iter_mut().for_each(|s| *s = s.patch(style));
+        }
+        super::styled_spans(&chars, &styles)
+    }
+
+    pub fn render(&mut self, frame: &mut Frame) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(1)].as_ref())
+            .split(frame.size());
+        let subject = self.mail.subject.clone().unwrap_or_else(|| "(no subject)".into());
+        let from = self.mail.from.clone().unwrap_or_else(|| "unknown".into());
+        let block = Block::default().title(format!("{} — {}", subject, from)).borders(Borders::ALL);
+        let inner = block.inner(chunks[0]);
+
+        if inner.width != self.wrapped_for {
+            // keep roughly the same place in the text across a resize
+            let ratio = if self.lines.is_empty() { 0.0 } else { self.scroll as f64 / self.lines.len() as f64 };
+            (self.lines, self.origin) = wrap(&self.source, inner.width as usize);
+            self.wrapped_for = inner.width;
+            self.scroll = (ratio * self.lines.len() as f64) as usize;
+            if self.query.is_some() {
+                let current = self.current;
+                self.matches = self.query.as_deref().map(|q| find_matches(&self.lines, q)).unwrap_or_default();
+                self.current = current.min(self.matches.len().saturating_sub(1));
+            }
+        }
+        self.height = (inner.height as usize).max(1);
+        self.scroll = self.scroll.min(self.max_scroll());
+
+        let end = (self.scroll + self.height).min(self.lines.len());
+        let visible: Vec<Line> = (self.scroll..end).map(|n| self.styled_line(n)).collect();
+        frame.render_widget(Paragraph::new(visible).block(block), chunks[0]);
+
+        let position = if self.lines.len() <= self.height {
+            "All".to_string()
+        } else {
+            let pct = end * 100 / self.lines.len();
+            format!("{}-{}/{} {}%", self.scroll + 1, end, self.lines.len(), pct)
+        };
+        let left = match (&self.prompt, &self.query) {
+            (Some(p), _) => format!("/{}", p),
+            (None, Some(q)) if self.matches.is_empty() => format!("no match for \"{}\"   Esc: clear", q),
+            (None, Some(q)) => format!("\"{}\" {}/{}   n/N: next/prev match   Esc: clear", q, self.current + 1, self.matches.len()),
+            (None, None) => match self.keys.pending() {
+                Some(keys) => format!("{}-", keys),
+                None => "?: keys".to_string(),
+            },
+        };
+        let footer = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Min(0), Constraint::Length(position.chars().count() as u16 + 1)].as_ref())
+            .split(chunks[1]);
+        frame.render_widget(Paragraph::new(left).style(crate::theme::get().dim), footer[0]);
+        frame.render_widget(Paragraph::new(position), footer[1]);
+        if self.help {
+            super::help::render(frame, "Reader keys", Context::Reader);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use unicode_width::UnicodeWidthStr;
+
+    fn wrapped(text: &str, width: usize) -> Vec<String> {
+        wrap(&[text.to_string()], width).0
+    }
+
+    #[test]
+    fn wraps_at_spaces() {
+        assert_eq!(wrapped("the quick brown fox", 10), vec!["the quick", "brown fox"]);
+        assert_eq!(wrapped("abcdefghij", 4), vec!["abcd", "efgh", "ij"]);
+    }
+
+    #[test]
+    fn wide_characters_take_two_cells() {
+        let lines = wrapped("日本語のテキストです", 8);
+        assert_eq!(lines, vec!["日本語の", "テキスト", "です"]);
+        for text in ["日本語 のテキスト です", "🎉🎉 party 🎉🎉🎉", "mixed 中文 text here"] {
+            for width in 2..12 {
+                for line in wrapped(text, width) {
+                    assert!(line.width() <= width, "{:?} in {}", line, width);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn a_character_wider_than_the_pane_still_wraps() {
+        assert_eq!(wrapped("日本", 1), vec!["日", "本"]);
+    }
+}
@@ -0,0 +1,224 @@
+//! The status line under the mailbox list, and the message log behind it.
+
+use chrono::{DateTime, Local};
+use crossterm::event::KeyEvent;
+use once_cell::sync::Lazy;
+use ratatui::{
+    Frame,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+};
+use std::sync::Mutex;
+
+use crate::keymap::{Action, Context, Dispatcher};
+use std::time::{Duration, Instant};
+
+// how long a notice stays in the status line
+const NOTICE_TTL: Duration = Duration::from_secs(5);
+const LOG_CAPACITY: usize = 500;
+const SPINNER: &[char] = &['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+static STATUS: Lazy<Mutex<Status>> = Lazy::new(|| Mutex::new(Status::default()));
+
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    pub time: DateTime<Local>,
+    pub error: bool,
+    pub text: String,
+}
+
+#[derive(Default)]
+struct Status {
+    syncing: Option<Instant>,
+    last_sync: Option<DateTime<Local>>,
+    // error of the latest sync, if it failed
+    sync_error: Option<String>,
+    notice: Option<(LogEntry, Instant)>,
+    log: Vec<LogEntry>,
+    // errors added since the log was last opened
+    unseen_errors: usize,
+}
+
+fn push(text: String, error: bool) {
+    let entry = LogEntry { time: Local::now(), error, text };
+    let mut s = STATUS.lock().unwrap();
+    if error {
+        s.unseen_errors += 1;
+    }
+    s.notice = Some((entry.clone(), Instant::now()));
+    s.log.push(entry);
+    if s.log.len() > LOG_CAPACITY {
+        let excess = s.log.len() - LOG_CAPACITY;
+        s.log.drain(..excess);
+    }
+    drop(s);
+    redraw();
+}
+
+fn redraw() {
+    crate::events::send(crate::events::AppEvent::Redraw);
+}
+
+/// True while the status line changes by itself: the sync spinner turns, or a notice is due to disappear.
+pub fn ticking() -> bool {
+    let s = STATUS.lock().unwrap();
+    s.syncing.is_some() || s.notice.as_ref().is_some_and(|(_, at)| at.elapsed() > NOTICE_TTL)
+}
+
+/// Flash a message in the status line; it is kept in the message log too.
+pub fn info(text: impl Into<String>) {
+    let text = text.into();
+    log::info!("{}", text);
+    push(text, false);
+}
+
+/// Like `info`, but shown in the error color, counted in the status line and written to the log file as an error.
+pub fn error(text: impl Into<String>) {
+    let text = text.into();
+    log::error!("{}", text);
+    push(text, true);
+}
+
+pub fn sync_started() {
+    STATUS.lock().unwrap().syncing = Some(Instant::now());
+    redraw();
+}
+
+/// Record the end of a sync. A failure goes to the message log unless it repeats the previous one,
+/// so a dropped connection doesn't add an entry every interval.
+pub fn sync_finished(result: Result<(), String>) {
+    let new_error = {
+        let mut s = STATUS.lock().unwrap();
+        s.syncing = None;
+        let error = result.err();
+        if error.is_none() {
+            s.last_sync = Some(Local::now());
+        }
+        let repeated = error.is_some() && error == s.sync_error;
+        s.sync_error = error.clone();
+        error.filter(|_| !repeated)
+    };
+    match new_error {
+        Some(e) => error(format!("sync failed: {}", e)),
+        None => redraw(),
+    }
+}
+
+/// The log, oldest first. Marks its errors as seen.
+pub fn take_log() -> Vec<LogEntry> {
+    let mut s = STATUS.lock().unwrap();
+    s.unseen_errors = 0;
+    s.log.clone()
+}
+
+/// One line: account, mailbox and counts on the left, the latest notice, then keys typed so far
+/// and sync state on the right.
+pub fn render(frame: &mut Frame, area: Rect, mailbox: &str, counts: &str, pending_keys: Option<&str>) {
+    let theme = crate::theme::get();
+    let mut s = STATUS.lock().unwrap();
+    if s.notice.as_ref().is_some_and(|(_, at)| at.elapsed() > NOTICE_TTL) {
+        s.notice = None;
+    }
+
+    let mut left = Vec::new();
+    if let Some(a) = crate::config::get().account() {
+        left.push(Span::styled(format!(" {} ", a.name), Style::default().add_modifier(Modifier::BOLD)));
+        left.push(Span::raw("· "));
+    } else {
+        left.push(Span::raw(" "));
+    }
+    left.push(Span::raw(format!("{} · {}", mailbox, counts)));
+    if let Some((notice, _)) = &s.notice {
+        let style = if notice.error { theme.error } else { theme.accent };
+        left.push(Span::raw("  "));
+        left.push(Span::styled(notice.text.replace('\n', " "), style));
+    }
+
+    let mut right = vec![Span::raw("  ")];
+    if s.unseen_errors > 0 {
+        let hint = match crate::config::get().keybindings.keys_for(Action::MessageLog, Context::List).first() {
+            Some(key) => format!("{} error(s), {} for log  ", s.unseen_errors, key),
+            None => format!("{} error(s)  ", s.unseen_errors),
+        };
+        right.push(Span::styled(hint, theme.error));
+    }
+    if let Some(keys) = pending_keys {
+        right.push(Span::styled(format!("{}-  ", keys), Style::default().add_modifier(Modifier::BOLD)));
+    }
+    let sync = match (s.syncing, s.last_sync) {
+        (Some(started), _) => {
+            let frame = SPINNER[(started.elapsed().as_millis() / 100) as usize % SPINNER.len()];
+            Span::raw(format!("{} syncing ", frame))
+        }
+        (None, Some(t)) if s.sync_error.is_some() => Span::styled(format!("sync failed, last ok {} ", t.format("%H:%M")), theme.error),
+        (None, Some(t)) => Span::styled(format!("synced {} ", t.format("%H:%M")), theme.dim),
+        (None, None) if s.sync_error.is_some() => Span::styled("sync failed ".to_string(), theme.error),
+        (None, None) => Span::styled("not synced ".to_string(), theme.dim),
+    };
+    right.push(sync);
+    drop(s);
+
+    let right = Line::from(right);
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Min(0), Constraint::Length(right.width() as u16)].as_ref())
+        .split(area);
+    frame.render_widget(Paragraph::new(Line::from(left)).style(theme.status_bar), chunks[0]);
+    frame.render_widget(Paragraph::new(right).style(theme.status_bar), chunks[1]);
+}
+
+/// Scrollable list of past notices and errors, newest at the bottom.
+pub struct MessageLogView {
+    entries: Vec<LogEntry>,
+    state: ListState,
+    keys: Dispatcher,
+    help: bool,
+}
+
+impl MessageLogView {
+    pub fn new(entries: Vec<LogEntry>) -> Self {
+        let mut state = ListState::default();
+        state.select(entries.len().checked_sub(1));
+        MessageLogView { entries, state, keys: Dispatcher::default(), help: false }
+    }
+
+    /// Returns true when the view should close.
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        if self.help {
+            self.help = false;
+            return false;
+        }
+        match self.keys.feed(&crate::config::get().keybindings, Context::MessageLog, key).first() {
+            Some(Action::Back | Action::Quit) => return true,
+            Some(Action::Help) => self.help = true,
+            Some(a) => super::move_selection(&mut self.state, self.entries.len(), *a),
+            None => {}
+        }
+        false
+    }
+
+    pub fn render(&mut self, frame: &mut Frame) {
+        let theme = crate::theme::get();
+        let items: Vec<ListItem> = if self.entries.is_empty() {
+            vec![ListItem::new("No messages yet")]
+        } else {
+            self.entries
+                .iter()
+                .map(|e| {
+                    let line = format!("{} {}", e.time.format("%H:%M:%S"), e.text);
+                    let style = if e.error { theme.error } else { Style::default() };
+                    ListItem::new(line).style(style)
+                })
+                .collect()
+        };
+        let list = List::new(items)
+            .block(Block::default().title(format!("Messages — {}", super::help::hints(Context::MessageLog, &[(Action::Back, "back")]))).borders(Borders::ALL))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, frame.size(), &mut self.state);
+        if self.help {
+            super::help::render(frame, "Message log keys", Context::MessageLog);
+        }
+    }
+}
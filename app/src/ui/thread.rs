@@ -0,0 +1,113 @@
+use crossterm::event::KeyEvent;
+use ratatui::{
+    Frame,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, Wrap},
+};
+
+use crate::gmail::SimpleMail;
+use crate::keymap::{Action, Context, Dispatcher};
+
+// headers skipped by page up / page down
+const PAGE: usize = 10;
+
+/// Whole-conversation view: messages in chronological order, older ones collapsed to one line.
+pub struct ThreadView {
+    subject: String,
+    messages: Vec<SimpleMail>,
+    expanded: Vec<bool>,
+    selected: usize,
+    keys: Dispatcher,
+    help: bool,
+}
+
+impl ThreadView {
+    pub fn new(messages: Vec<SimpleMail>) -> Self {
+        let subject = messages
+            .first()
+            .and_then(|m| m.subject.clone())
+            .unwrap_or_else(|| "(no subject)".into());
+        let last = messages.len().saturating_sub(1);
+        let expanded = (0..messages.len()).map(|i| i == last).collect();
+        ThreadView { subject, messages, expanded, selected: last, keys: Dispatcher::default(), help: false }
+    }
+
+    /// Returns true when the view should be closed.
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        if self.help {
+            self.help = false;
+            return false;
+        }
+        let last = self.messages.len().saturating_sub(1);
+        match self.keys.feed(&crate::config::get().keybindings, Context::Thread, key).first() {
+            Some(Action::Back | Action::Quit) => return true,
+            Some(Action::Help) => self.help = true,
+            Some(Action::Up) => self.selected = self.selected.saturating_sub(1),
+            Some(Action::Down) => self.selected = (self.selected + 1).min(last),
+            Some(Action::PageUp) => self.selected = self.selected.saturating_sub(PAGE),
+            Some(Action::PageDown) => self.selected = (self.selected + PAGE).min(last),
+            Some(Action::Top) => self.selected = 0,
+            Some(Action::Bottom) => self.selected = last,
+            Some(Action::Open) => {
+                if let Some(e) = self.expanded.get_mut(self.selected) {
+                    *e = !*e;
+                }
+            }
+            Some(Action::ExpandAll) => {
+                let all = self.expanded.iter().all(|e| *e);
+                self.expanded.iter_mut().for_each(|e| *e = !all);
+            }
+            _ => {}
+        }
+        false
+    }
+
+    pub fn render(&self, frame: &mut Frame) {
+        let size = frame.size();
+        let mut lines: Vec<Line> = Vec::new();
+        let mut selected_line = 0;
+
+        for (i, m) in self.messages.iter().enumerate() {
+            let from = m.from.clone().unwrap_or_else(|| "unknown".into());
+            let date = m.date.clone().unwrap_or_default();
+            let open = self.expanded[i];
+            let marker = if open { "▾" } else { "▸" };
+
+            let mut header_style = Style::default().add_modifier(Modifier::BOLD);
+            if i == self.selected {
+                header_style = header_style.patch(crate::theme::get().accent);
+                selected_line = lines.len();
+            }
+
+            if open {
+                lines.push(Line::from(Span::styled(format!("{} {} — {}", marker, from, date), header_style)));
+                let text = m.body.clone().or_else(|| m.snippet.clone()).unwrap_or_default();
+                for l in text.lines() {
+                    lines.push(Line::from(format!("  {}", l)));
+                }
+                lines.push(Line::from(""));
+            } else {
+                let snippet = m.snippet.clone().unwrap_or_default();
+                lines.push(Line::from(vec![
+                    Span::styled(format!("{} {} — {}  ", marker, from, date), header_style),
+                    Span::styled(snippet, crate::theme::get().dim),
+                ]));
+            }
+        }
+
+        // keep the selected header on screen
+        let inner_height = size.height.saturating_sub(2) as usize;
+        let scroll = selected_line.saturating_sub(inner_height / 2) as u16;
+
+        let title = format!("{} ({} messages)", self.subject, self.messages.len());
+        let paragraph = Paragraph::new(lines)
+            .block(Block::default().title(title).borders(Borders::ALL))
+            .wrap(Wrap { trim: false })
+            .scroll((scroll, 0));
+        frame.render_widget(paragraph, size);
+        if self.help {
+            super::help::render(frame, "Thread keys", Context::Thread);
+        }
+    }
+}